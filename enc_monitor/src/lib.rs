@@ -12,12 +12,18 @@ use vst::buffer::AudioBuffer;
 use vst::host::Host;
 use vst::plugin::{CanDo, Category, HostCallback, Info, Plugin, PluginParameters};
 use vst::util::AtomicFloat;
+use lame::stereo::StereoMode;
+use lame::filter::Cutoff;
+use lame::preset::Preset;
+use lame::info;
 use crate::process::ProcessStereo;
 use crate::process_bypass::BypassProcessor;
 use crate::process_mp3::Mp3Processor;
 use crate::stereo_buffer::StereoBuffer;
 
 const DELAY_SAMPLES: usize = 11025;
+// LAME preset to monitor; None encodes 320kbps CBR
+const PRESET: Option<Preset> = None;
 
 
 struct EncMonitorParameters {
@@ -96,14 +102,24 @@ impl Plugin for EncMonitor {
     }
     fn new(_host: HostCallback) -> Self {
         println!("Initializing enc_monitor...");
+        println!("  LAME {} (psy {})", info::version(), info::psy_version());
 
         //let time_info = _host.get_time_info(!0)// TODO
         //    .unwrap();
 
         let mut processor_mp3 = Mp3Processor::new()
             .unwrap();
-        processor_mp3.set_parameters(44100, 320)
+        match PRESET {
+            Some(preset) => processor_mp3.set_preset(44100, preset, StereoMode::JointStereo),
+            None => processor_mp3.set_parameters(44100, 320, StereoMode::JointStereo, Cutoff::Auto),
+        }
             .unwrap();
+        if let Some(mode) = processor_mp3.stereo_mode() {
+            println!("  Stereo mode: {:?}", mode);
+        }
+        if let Cutoff::Frequency(freq) = processor_mp3.lowpass() {
+            println!("  Lowpass: {}Hz", freq);
+        }
 
         //println!("  Sample Rate: {}Hz", time_info.sample_rate); // 0Hz
 
@@ -125,6 +141,13 @@ impl Plugin for EncMonitor {
         }
     }
 
+    // Called outside the audio thread, so the stats can be printed here
+    // without blocking processing.
+    fn suspend(&mut self) {
+        let stats = self.processor_mp3.stats();
+        println!("Frames: {} (M/S: {})", stats.frames, stats.stereo_modes.mid_side);
+    }
+
     fn process(&mut self, buffer: &mut AudioBuffer<f32>) {
         let (inputs, outputs) = buffer.split();
         let inputs = (&inputs[0], &inputs[1]);
@@ -1,9 +1,52 @@
+use std::error;
+use std::fmt;
+use lame::decode::DecodeError;
+use lame::encode::EncodeError;
+
 #[derive(Debug)]
 pub enum Error {
-    // TODO: add error types
-    InternalError,
-    NoMem,
-    Other(i32),
+    // the encoder could not be created or configured
+    Lame(lame::Error),
+    Encode(EncodeError),
+    Decode(DecodeError),
+}
+
+impl From<lame::Error> for Error {
+    fn from(err: lame::Error) -> Error {
+        Error::Lame(err)
+    }
+}
+
+impl From<EncodeError> for Error {
+    fn from(err: EncodeError) -> Error {
+        Error::Encode(err)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(err: DecodeError) -> Error {
+        Error::Decode(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Lame(_) => write!(f, "failed to configure the MP3 encoder"),
+            Error::Encode(_) => write!(f, "failed to encode MP3"),
+            Error::Decode(_) => write!(f, "failed to decode MP3"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Lame(err) => Some(err),
+            Error::Encode(err) => Some(err),
+            Error::Decode(err) => Some(err),
+        }
+    }
 }
 
 pub trait ProcessStereo {
@@ -1,17 +1,21 @@
-use lame::Lame;
+use lame::{Lame, Uninitialized};
 use crate::process::{Error, ProcessStereo};
 use Vec;
 use lame::decode::Decode;
-use lame::encode::{Encode, EncodeError};
+use lame::encode::Encode;
+use lame::stereo::StereoMode;
+use lame::filter::Cutoff;
+use lame::preset::Preset;
+use lame::stats::EncoderStats;
 
-const BYTE_BUF_SIZE: usize = 12500 + 7200;
 const PCM_BUF_SIZE: usize = 44100 * 2;
 
 pub struct Mp3Processor {
     lame: Lame,
     byte_buffer: Vec<u8>,
-    pcm_buffer_l: Vec<i16>,
-    pcm_buffer_r: Vec<i16>,
+    pcm_buffer_l: Vec<f32>,
+    pcm_buffer_r: Vec<f32>,
+    mono_buffer: Vec<f32>,
 }
 
 impl Default for Mp3Processor {
@@ -23,46 +27,85 @@ impl Default for Mp3Processor {
 
 impl Mp3Processor {
     pub fn new() -> Option<Mp3Processor> {
-        match Lame::new() {
+        match Lame::new().and_then(|lame| lame.init_params().ok()) {
             None => None,
             Some(lame) => Some(Mp3Processor {
                 lame,
-                byte_buffer: vec![0; BYTE_BUF_SIZE],
-                pcm_buffer_l: vec![0; PCM_BUF_SIZE],
-                pcm_buffer_r: vec![0; PCM_BUF_SIZE],
+                byte_buffer: Vec::new(),
+                pcm_buffer_l: vec![0.0; PCM_BUF_SIZE],
+                pcm_buffer_r: vec![0.0; PCM_BUF_SIZE],
+                mono_buffer: Vec::with_capacity(PCM_BUF_SIZE),
             }),
         }
     }
 
-    pub fn set_parameters(&mut self, sample_rate: u32, kilobitrate: u32) -> Result<(), Error> {
-        self.lame.init_params().map_err(|_| { Error::InternalError })?;
-        self.lame.set_channels(2).map_err(|_| { Error::InternalError })?;
-        self.lame.set_sample_rate(sample_rate).map_err(|_| { Error::InternalError })?;
-        self.lame.set_kilobitrate(kilobitrate as i32).map_err(|_| { Error::InternalError })?;
+    // replaces the encoder with a new one configured with the given parameters
+    // StereoMode::Mono encodes a mono downmix of the input
+    // lowpass: Cutoff::Auto keeps the bandwidth LAME picks for the bitrate
+    pub fn set_parameters(&mut self, sample_rate: u32, kilobitrate: u32, stereo_mode: StereoMode, lowpass: Cutoff) -> Result<(), Error> {
+        let mut lame = new_lame(sample_rate, stereo_mode)?;
+        lame.set_lowpass(lowpass)?;
+        lame.set_kilobitrate(kilobitrate as i32)?;
+        self.lame = lame.init_params()?;
         Ok(())
     }
 
+    // replaces the encoder with a new one configured by a LAME preset
+    pub fn set_preset(&mut self, sample_rate: u32, preset: Preset, stereo_mode: StereoMode) -> Result<(), Error> {
+        let mut lame = new_lame(sample_rate, stereo_mode)?;
+        lame.set_preset(preset)?;
+        self.lame = lame.init_params()?;
+        Ok(())
+    }
+
+    // channel mode the encoder actually uses
+    pub fn stereo_mode(&self) -> Option<StereoMode> {
+        self.lame.stereo_mode()
+    }
+
+    // bandwidth cutoff the encoder actually uses
+    pub fn lowpass(&self) -> Cutoff {
+        self.lame.lowpass()
+    }
+
+    // statistics of the frames encoded so far
+    pub fn stats(&self) -> EncoderStats {
+        self.lame.stats()
+    }
+
     pub fn process_iter(&mut self, input_buffers: (&[f32], &[f32])) -> Result<impl Iterator<Item=(f32, f32)> + '_, Error> {
         // encode into `bytes`
-        let byte_size = self.lame.encode_flushing_nogap(input_buffers.0, input_buffers.1, &mut self.byte_buffer)
-            .map_err(|e| match e {
-                EncodeError::NoMem => Error::NoMem,
-                _ => Error::InternalError,
-            })?;
-        let bytes = &self.byte_buffer[..byte_size];
+        self.byte_buffer.clear();
+        let encoded = if self.lame.channels() == 1 {
+            self.mono_buffer.clear();
+            self.mono_buffer.extend(input_buffers.0.iter().zip(input_buffers.1.iter())
+                .map(|(&l, &r)| (l + r) * 0.5));
+            self.lame.encode_mono_to_vec(&self.mono_buffer, &mut self.byte_buffer)
+        } else {
+            self.lame.encode_to_vec(input_buffers.0, input_buffers.1, &mut self.byte_buffer)
+        };
+        encoded?;
+        self.lame.flush_nogap_to_vec(&mut self.byte_buffer)?;
+        let bytes = &self.byte_buffer[..];
 
-        // decode into `samples`
-        let len = self.lame.decode(bytes, &mut self.pcm_buffer_l[..], &mut self.pcm_buffer_r[..])
-            .unwrap();
+        // decode into `samples` (mono is decoded into both channels)
+        let len = self.lame.decode(bytes, &mut self.pcm_buffer_l[..], &mut self.pcm_buffer_r[..])?;
         let samples = self.pcm_buffer_l.iter().take(len).zip(self.pcm_buffer_r.iter().take(len))
-            .map(|(&l, &r)| (
-                (l as f32) / (i16::MAX as f32),
-                (r as f32) / (i16::MAX as f32)
-            ));
+            .map(|(&l, &r)| (l, r));
         Ok(samples)
     }
 }
 
+// creates an unconfigured encoder for the given input rate and channel mode
+fn new_lame(sample_rate: u32, stereo_mode: StereoMode) -> Result<Lame<Uninitialized>, Error> {
+    let channels = if stereo_mode == StereoMode::Mono { 1 } else { 2 };
+    let mut lame = Lame::new().ok_or(lame::Error::NoMem)?;
+    lame.set_channels(channels)?;
+    lame.set_stereo_mode(stereo_mode)?;
+    lame.set_sample_rate(sample_rate)?;
+    Ok(lame)
+}
+
 impl ProcessStereo for Mp3Processor {
     fn process(&mut self, input_buffers: (&[f32], &[f32]), output_buffers: (&mut [f32], &mut [f32])) -> Result<usize, Error> {
         // write into output_buffers
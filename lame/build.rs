@@ -1,7 +1,116 @@
 use std::env::var;
 
+// LAME itself can be linked in one of three ways:
+// - `vendored`: built from source with the `cc` crate. The sources of a LAME
+//   release are taken from `vendor/lame`, or from the directory in the
+//   `LAME_SRC_DIR` environment variable.
+// - `system`: an installed libmp3lame, found with pkg-config if it ships a
+//   `.pc` file and in the default library paths otherwise.
+// - neither (default): the prebuilt static library in `lib/`.
+// `vendored` takes precedence if both features are enabled.
+//
+// The static builds also export hip's unclipped decoder, which is missing
+// from the export list of the shared library. They set `hip_unclipped`.
 fn main() {
+    println!("cargo:rustc-check-cfg=cfg(hip_unclipped)");
+
+    #[cfg(feature = "vendored")]
+    {
+        vendored::build();
+        println!("cargo:rustc-cfg=hip_unclipped");
+        return;
+    }
+
+    #[cfg(all(feature = "system", not(feature = "vendored")))]
+    {
+        system::link();
+        return;
+    }
+
+    #[allow(unreachable_code)]
+    {
+        link_prebuilt();
+        println!("cargo:rustc-cfg=hip_unclipped");
+    }
+}
+
+fn link_prebuilt() {
     let manifest_dir = var("CARGO_MANIFEST_DIR").unwrap();
     println!("cargo:rustc-link-lib=static=libmp3lame");
     println!("cargo:rustc-link-search={}/lib", manifest_dir);
-}
\ No newline at end of file
+}
+
+#[cfg(all(feature = "system", not(feature = "vendored")))]
+mod system {
+    pub fn link() {
+        // most distributions do not ship a pkg-config file for LAME
+        if pkg_config::Config::new().probe("mp3lame").is_err() {
+            println!("cargo:rustc-link-lib=mp3lame");
+        }
+    }
+}
+
+#[cfg(feature = "vendored")]
+mod vendored {
+    use std::env::var;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    const LIBMP3LAME_SOURCES: &[&str] = &[
+        "bitstream.c", "encoder.c", "fft.c", "gain_analysis.c", "id3tag.c", "lame.c",
+        "mpglib_interface.c", "newmdct.c", "presets.c", "psymodel.c", "quantize.c",
+        "quantize_pvt.c", "reservoir.c", "set_get.c", "tables.c", "takehiro.c", "util.c",
+        "vbrquantize.c", "VbrTag.c", "version.c",
+    ];
+
+    const MPGLIB_SOURCES: &[&str] = &[
+        "common.c", "dct64_i386.c", "decode_i386.c", "interface.c", "layer1.c", "layer2.c",
+        "layer3.c", "tabinit.c",
+    ];
+
+    // Replaces the config.h that LAME's configure script would generate. The
+    // hip decoder (mpglib) is required by the `decode` module.
+    const CONFIG_H: &str = "\
+#define STDC_HEADERS 1
+#define HAVE_STDINT_H 1
+#define HAVE_STDLIB_H 1
+#define HAVE_STRING_H 1
+#define HAVE_ERRNO_H 1
+#define HAVE_LIMITS_H 1
+#define HAVE_MPGLIB 1
+#define DECODE_ON_THE_FLY 1
+#define USE_FAST_LOG 1
+#define TAKEHIRO_IEEE754_HACK 1
+#include <stdint.h>
+typedef long double ieee854_float80_t;
+typedef double ieee754_float64_t;
+typedef float ieee754_float32_t;
+";
+
+    pub fn build() {
+        println!("cargo:rerun-if-env-changed=LAME_SRC_DIR");
+        let src_dir = match var("LAME_SRC_DIR") {
+            Ok(dir) => PathBuf::from(dir),
+            Err(_) => Path::new(&var("CARGO_MANIFEST_DIR").unwrap()).join("vendor/lame"),
+        };
+        if !src_dir.join("include/lame.h").exists() {
+            panic!("LAME sources not found in {}; unpack a LAME release there or set LAME_SRC_DIR", src_dir.display());
+        }
+        println!("cargo:rerun-if-changed={}", src_dir.display());
+
+        let out_dir = PathBuf::from(var("OUT_DIR").unwrap());
+        fs::write(out_dir.join("config.h"), CONFIG_H).unwrap();
+
+        cc::Build::new()
+            .files(LIBMP3LAME_SOURCES.iter().map(|file| src_dir.join("libmp3lame").join(file)))
+            .files(MPGLIB_SOURCES.iter().map(|file| src_dir.join("mpglib").join(file)))
+            .include(&out_dir)
+            .include(src_dir.join("include"))
+            .include(src_dir.join("libmp3lame"))
+            .include(src_dir.join("mpglib"))
+            .include(&src_dir)
+            .define("HAVE_CONFIG_H", None)
+            .warnings(false)
+            .compile("mp3lame");
+    }
+}
@@ -0,0 +1,127 @@
+use crate::ffi;
+use crate::{handle_simple_error, Error, Lame, Uninitialized};
+use std::os::raw::c_int;
+
+/// De-emphasis the decoder should apply, as signalled in the frame header.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Emphasis {
+    None,
+    /// 50/15 µs emphasis.
+    Ms50_15,
+    /// CCITT J.17 emphasis.
+    CcittJ17,
+}
+
+impl Emphasis {
+    fn to_raw(self) -> c_int {
+        match self {
+            Emphasis::None => 0,
+            Emphasis::Ms50_15 => 1,
+            Emphasis::CcittJ17 => 3,
+        }
+    }
+
+    fn from_raw(emphasis: c_int) -> Emphasis {
+        match emphasis {
+            1 => Emphasis::Ms50_15,
+            3 => Emphasis::CcittJ17,
+            _ => Emphasis::None,
+        }
+    }
+}
+
+impl Lame<Uninitialized> {
+    /// Adds a CRC to every frame so that decoders can detect corrupted
+    /// headers and side info. Costs 16 bits per frame. Defaults to false.
+    pub fn set_error_protection(&mut self, error_protection: bool) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_error_protection(self.ptr, error_protection as c_int)
+        })
+    }
+
+    /// Sets the copyright bit of the frame headers. Defaults to false.
+    pub fn set_copyright(&mut self, copyright: bool) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_copyright(self.ptr, copyright as c_int)
+        })
+    }
+
+    /// Sets the original bit of the frame headers, which marks the stream as
+    /// an original rather than a copy. Defaults to true.
+    pub fn set_original(&mut self, original: bool) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_original(self.ptr, original as c_int)
+        })
+    }
+
+    /// Sets the emphasis signalled in the frame headers. LAME does not apply
+    /// the emphasis to the audio itself. Defaults to `Emphasis::None`.
+    pub fn set_emphasis(&mut self, emphasis: Emphasis) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_emphasis(self.ptr, emphasis.to_raw())
+        })
+    }
+
+    /// Enforces strict ISO compliance, e.g. by limiting the size of the bit
+    /// reservoir, for decoders that do not accept LAME's extensions.
+    /// Defaults to false.
+    pub fn set_strict_iso(&mut self, strict_iso: bool) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_strict_ISO(self.ptr, strict_iso as c_int)
+        })
+    }
+
+    /// Sets the private extension bit of the frame headers. Defaults to
+    /// false.
+    pub fn set_extension(&mut self, extension: bool) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_extension(self.ptr, extension as c_int)
+        })
+    }
+
+    /// Encodes a free format stream, whose CBR bitrate does not have to be
+    /// one of the standard bitrates. Many decoders cannot play free format
+    /// streams. Defaults to false.
+    pub fn set_free_format(&mut self, free_format: bool) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_free_format(self.ptr, free_format as c_int)
+        })
+    }
+}
+
+impl<State> Lame<State> {
+    /// Whether every frame carries a CRC.
+    pub fn error_protection(&self) -> bool {
+        unsafe { ffi::lame_get_error_protection(self.ptr) != 0 }
+    }
+
+    /// Copyright bit of the frame headers.
+    pub fn copyright(&self) -> bool {
+        unsafe { ffi::lame_get_copyright(self.ptr) != 0 }
+    }
+
+    /// Original bit of the frame headers.
+    pub fn original(&self) -> bool {
+        unsafe { ffi::lame_get_original(self.ptr) != 0 }
+    }
+
+    /// Emphasis signalled in the frame headers.
+    pub fn emphasis(&self) -> Emphasis {
+        Emphasis::from_raw(unsafe { ffi::lame_get_emphasis(self.ptr) })
+    }
+
+    /// Whether strict ISO compliance is enforced.
+    pub fn strict_iso(&self) -> bool {
+        unsafe { ffi::lame_get_strict_ISO(self.ptr) != 0 }
+    }
+
+    /// Private extension bit of the frame headers.
+    pub fn extension(&self) -> bool {
+        unsafe { ffi::lame_get_extension(self.ptr) != 0 }
+    }
+
+    /// Whether a free format stream is encoded.
+    pub fn free_format(&self) -> bool {
+        unsafe { ffi::lame_get_free_format(self.ptr) != 0 }
+    }
+}
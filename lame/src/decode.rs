@@ -1,28 +1,272 @@
-use crate::ffi;
 use crate::Lame;
+use crate::stereo::StereoMode;
+use std::fmt;
 use std::os::raw::c_int;
 
+#[cfg(feature = "hip")]
+mod hip;
+#[cfg(feature = "puremp3")]
+mod pure;
+
+#[cfg(feature = "hip")]
+pub use self::hip::HipDecoder;
+#[cfg(feature = "hip")]
+pub(crate) use self::hip::HIP_LOCK;
+#[cfg(feature = "puremp3")]
+pub use self::pure::PureMp3Decoder;
+
+#[cfg(not(any(feature = "hip", feature = "puremp3")))]
+compile_error!("enable the `hip` or `puremp3` feature of lame to get an MP3 decoder");
+
+/// Maximum number of samples per channel in a single MPEG audio frame.
+pub(crate) const MAX_FRAME_SAMPLES: usize = 1152;
+
 #[derive(Debug)]
 pub enum DecodeError {
+    /// A decoded frame does not fit into the PCM buffers.
     OutputBufferTooSmall,
+    /// The stream ended without a single MPEG audio frame header.
     NoHeader,
+    /// A frame could not be decoded.
+    InvalidFrame,
+    /// The hip decoder failed with the given error code.
     Unknown(c_int),
 }
 
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::OutputBufferTooSmall => write!(f, "PCM output buffer too small"),
+            DecodeError::NoHeader => write!(f, "no MPEG audio frame header found"),
+            DecodeError::InvalidFrame => write!(f, "invalid MPEG audio frame"),
+            DecodeError::Unknown(code) => write!(f, "decoding failed (hip error {})", code),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Stream parameters read from the most recently parsed frame header.
+#[derive(Debug, Copy, Clone)]
+pub struct StreamInfo {
+    /// Number of channels, 1 or 2.
+    pub channels: u8,
+    /// Sample rate in Hz.
+    pub sample_rate: u32,
+    /// Bitrate of the frame in kilobits per second.
+    pub kilobitrate: u32,
+    /// Channel mode from the frame header: 0 = stereo, 1 = joint stereo,
+    /// 2 = dual channel, 3 = mono. See `StreamInfo::stereo_mode`.
+    pub mode: u8,
+    /// Mode extension from the frame header (intensity/M-S stereo flags).
+    pub mode_ext: u8,
+    /// Number of samples per channel in one frame.
+    pub frame_size: usize,
+    /// Number of frames in the stream, if a Xing/Info tag was found.
+    pub total_frames: Option<usize>,
+    /// Number of samples per channel in the stream, if a Xing/Info tag was
+    /// found. This includes the encoder delay and padding.
+    pub total_samples: Option<u64>,
+}
+
+impl StreamInfo {
+    /// Channel mode from the frame header. Frames encoded with
+    /// `StereoMode::ForcedJointStereo` are reported as `JointStereo`.
+    pub fn stereo_mode(&self) -> StereoMode {
+        StereoMode::from_raw(self.mode as c_int, false)
+    }
+}
+
+/// Result of `Decoder::decode_with_info`.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DecodeInfo {
+    /// Number of samples per channel written to the PCM buffers.
+    pub samples: usize,
+    /// Stream parameters, or None if no frame header has been parsed yet.
+    pub stream: Option<StreamInfo>,
+    /// Samples of encoder delay at the start of the stream, if the LAME tag
+    /// was found.
+    pub encoder_delay: Option<usize>,
+    /// Samples of padding at the end of the stream, if the LAME tag was
+    /// found.
+    pub encoder_padding: Option<usize>,
+}
+
+trait Sample: Copy {
+    /// Mixes a stereo sample down to mono.
+    fn mix(l: Self, r: Self) -> Self;
+}
+
+impl Sample for i16 {
+    fn mix(l: i16, r: i16) -> i16 {
+        ((l as i32 + r as i32) / 2) as i16
+    }
+}
+
+impl Sample for f32 {
+    fn mix(l: f32, r: f32) -> f32 {
+        (l + r) * 0.5
+    }
+}
+
+/// PCM buffers that decoded frames are written to.
+enum Output<'a, S> {
+    Stereo(&'a mut [S], &'a mut [S]),
+    Mono(&'a mut [S]),
+}
+
+impl<S: Sample> Output<'_, S> {
+    /// Writes a decoded frame at `offset`. Mono frames are copied to both
+    /// channels of a stereo output, and stereo frames are mixed down for a
+    /// mono output.
+    fn write(&mut self, offset: usize, frame_l: &[S], frame_r: &[S], mono: bool) -> Result<(), DecodeError> {
+        let end = offset + frame_l.len();
+        match self {
+            Output::Stereo(pcm_l, pcm_r) => {
+                if pcm_l.len() < end || pcm_r.len() < end {
+                    return Err(DecodeError::OutputBufferTooSmall);
+                }
+                pcm_l[offset..end].copy_from_slice(frame_l);
+                pcm_r[offset..end].copy_from_slice(if mono { frame_l } else { frame_r });
+            }
+            Output::Mono(pcm) => {
+                if pcm.len() < end {
+                    return Err(DecodeError::OutputBufferTooSmall);
+                }
+                if mono {
+                    pcm[offset..end].copy_from_slice(frame_l);
+                } else {
+                    for ((output, &l), &r) in pcm[offset..end].iter_mut().zip(frame_l).zip(frame_r) {
+                        *output = S::mix(l, r);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 pub trait Decode<S> {
+    /// Decodes MP3 data into PCM samples and returns the number of samples
+    /// written to each buffer. Mono streams are written to both buffers.
     fn decode(&mut self, mp3buffer: &[u8], pcm_buffer_l: &mut [S], pcm_buffer_r: &mut [S]) -> Result<usize, DecodeError>;
+
+    /// Decodes MP3 data into a single channel and returns the number of
+    /// samples written. Stereo streams are mixed down.
+    fn decode_mono(&mut self, mp3buffer: &[u8], pcm_buffer: &mut [S]) -> Result<usize, DecodeError>;
+}
+
+/// MP3 decoder backend, chosen at runtime. The available backends depend on
+/// the `hip` (default) and `puremp3` features.
+pub enum Decoder {
+    #[cfg(feature = "hip")]
+    Hip(HipDecoder),
+    #[cfg(feature = "puremp3")]
+    // boxed, as puremp3's state is much larger than hip's handle
+    PureMp3(Box<PureMp3Decoder>),
+}
+
+impl Decoder {
+    /// Creates a decoder with the default backend: hip if it is enabled,
+    /// puremp3 otherwise.
+    ///
+    /// Returns None if liblame could not allocate the hip decoder.
+    pub fn new() -> Option<Decoder> {
+        #[cfg(feature = "hip")]
+        return HipDecoder::new().map(Decoder::Hip);
+        #[cfg(not(feature = "hip"))]
+        return Some(Decoder::PureMp3(Box::default()));
+    }
+
+    /// Decodes MP3 data like `decode`, and also reports the parameters of the
+    /// decoded stream. The encoder delay and padding are only read by hip.
+    ///
+    /// Returns `DecodeError::OutputBufferTooSmall` if a decoded frame does not
+    /// fit into the PCM buffers.
+    pub fn decode_with_info(&mut self, mp3buffer: &[u8], pcm_buffer_l: &mut [i16], pcm_buffer_r: &mut [i16]) -> Result<DecodeInfo, DecodeError> {
+        match self {
+            #[cfg(feature = "hip")]
+            Decoder::Hip(decoder) => decoder.decode_with_info(mp3buffer, pcm_buffer_l, pcm_buffer_r),
+            #[cfg(feature = "puremp3")]
+            Decoder::PureMp3(decoder) => decoder.decode_with_info(mp3buffer, pcm_buffer_l, pcm_buffer_r),
+        }
+    }
+
+    /// Decodes at most one frame, see `HipDecoder::decode_frame`.
+    pub(crate) fn decode_frame(&mut self, mp3buffer: &[u8],
+                               frame_l: &mut [i16; MAX_FRAME_SAMPLES], frame_r: &mut [i16; MAX_FRAME_SAMPLES],
+                               info: &mut DecodeInfo) -> Result<usize, DecodeError> {
+        match self {
+            #[cfg(feature = "hip")]
+            Decoder::Hip(decoder) => decoder.decode_frame(mp3buffer, frame_l, frame_r, info),
+            #[cfg(feature = "puremp3")]
+            Decoder::PureMp3(decoder) => decoder.decode_frame(mp3buffer, frame_l, frame_r, info),
+        }
+    }
 }
 
-impl Decode<i16> for Lame {
+impl Decode<i16> for Decoder {
     fn decode(&mut self, mp3buffer: &[u8], pcm_buffer_l: &mut [i16], pcm_buffer_r: &mut [i16]) -> Result<usize, DecodeError> {
-        let retn = unsafe {
-            ffi::hip_decode(self.hip,
-                            mp3buffer.as_ptr(), mp3buffer.len(),
-                            pcm_buffer_l.as_mut_ptr(), pcm_buffer_r.as_mut_ptr())
-        };
-        if retn < 0 { return Err(DecodeError::Unknown(retn)); }
-        let decoded_samples = retn;
+        match self {
+            #[cfg(feature = "hip")]
+            Decoder::Hip(decoder) => decoder.decode(mp3buffer, pcm_buffer_l, pcm_buffer_r),
+            #[cfg(feature = "puremp3")]
+            Decoder::PureMp3(decoder) => decoder.decode(mp3buffer, pcm_buffer_l, pcm_buffer_r),
+        }
+    }
+
+    fn decode_mono(&mut self, mp3buffer: &[u8], pcm_buffer: &mut [i16]) -> Result<usize, DecodeError> {
+        match self {
+            #[cfg(feature = "hip")]
+            Decoder::Hip(decoder) => decoder.decode_mono(mp3buffer, pcm_buffer),
+            #[cfg(feature = "puremp3")]
+            Decoder::PureMp3(decoder) => decoder.decode_mono(mp3buffer, pcm_buffer),
+        }
+    }
+}
 
-        Ok(decoded_samples as usize)
+impl Decode<f32> for Decoder {
+    fn decode(&mut self, mp3buffer: &[u8], pcm_buffer_l: &mut [f32], pcm_buffer_r: &mut [f32]) -> Result<usize, DecodeError> {
+        match self {
+            #[cfg(feature = "hip")]
+            Decoder::Hip(decoder) => decoder.decode(mp3buffer, pcm_buffer_l, pcm_buffer_r),
+            #[cfg(feature = "puremp3")]
+            Decoder::PureMp3(decoder) => decoder.decode(mp3buffer, pcm_buffer_l, pcm_buffer_r),
+        }
     }
-}
\ No newline at end of file
+
+    fn decode_mono(&mut self, mp3buffer: &[u8], pcm_buffer: &mut [f32]) -> Result<usize, DecodeError> {
+        match self {
+            #[cfg(feature = "hip")]
+            Decoder::Hip(decoder) => decoder.decode_mono(mp3buffer, pcm_buffer),
+            #[cfg(feature = "puremp3")]
+            Decoder::PureMp3(decoder) => decoder.decode_mono(mp3buffer, pcm_buffer),
+        }
+    }
+}
+
+/// Decodes with the context's own `Decoder`.
+impl<S> Decode<S> for Lame where Decoder: Decode<S> {
+    fn decode(&mut self, mp3buffer: &[u8], pcm_buffer_l: &mut [S], pcm_buffer_r: &mut [S]) -> Result<usize, DecodeError> {
+        self.decoder.decode(mp3buffer, pcm_buffer_l, pcm_buffer_r)
+    }
+
+    fn decode_mono(&mut self, mp3buffer: &[u8], pcm_buffer: &mut [S]) -> Result<usize, DecodeError> {
+        self.decoder.decode_mono(mp3buffer, pcm_buffer)
+    }
+}
+
+impl<State> Lame<State> {
+    /// Replaces the decoder used by `Decode` and `decode_with_info`.
+    /// Defaults to `Decoder::new()`.
+    pub fn set_decoder(&mut self, decoder: Decoder) {
+        self.decoder = decoder;
+    }
+}
+
+impl Lame {
+    /// See `Decoder::decode_with_info`.
+    pub fn decode_with_info(&mut self, mp3buffer: &[u8], pcm_buffer_l: &mut [i16], pcm_buffer_r: &mut [i16]) -> Result<DecodeInfo, DecodeError> {
+        self.decoder.decode_with_info(mp3buffer, pcm_buffer_l, pcm_buffer_r)
+    }
+}
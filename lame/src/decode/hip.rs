@@ -0,0 +1,259 @@
+use super::{Decode, DecodeError, DecodeInfo, Output, Sample, StreamInfo, MAX_FRAME_SAMPLES};
+use crate::ffi;
+use crate::ffi::HipPtr;
+use crate::lock_global;
+use std::ops::Drop;
+use std::os::raw::c_int;
+use std::sync::Mutex;
+
+/// Number of times in a row hip can return no samples while it still holds
+/// data: when it skips the Xing/Info frame, after the very first header, and
+/// for a first frame whose bit reservoir is missing.
+const MAX_EMPTY_RESULTS: usize = 3;
+
+/// Serializes calls into hip. Its decoding functions use static output
+/// buffers, and `hip_decode_init` rewrites the global synthesis tables.
+pub(crate) static HIP_LOCK: Mutex<()> = Mutex::new(());
+
+// c_ulong is only 32 bits wide on Windows
+#[allow(clippy::unnecessary_cast)]
+fn stream_info(mp3data: &ffi::mp3data_struct) -> StreamInfo {
+    StreamInfo {
+        channels: mp3data.stereo as u8,
+        sample_rate: mp3data.samplerate as u32,
+        kilobitrate: mp3data.bitrate as u32,
+        mode: mp3data.mode as u8,
+        mode_ext: mp3data.mode_ext as u8,
+        frame_size: mp3data.framesize as usize,
+        total_frames: if mp3data.totalframes > 0 { Some(mp3data.totalframes as usize) } else { None },
+        total_samples: if mp3data.nsamp > 0 { Some(mp3data.nsamp as u64) } else { None },
+    }
+}
+
+fn to_i16(sample: i16) -> i16 {
+    sample
+}
+
+#[cfg(not(hip_unclipped))]
+fn to_f32(sample: i16) -> f32 {
+    sample as f32 / 32768.0
+}
+
+/// LAME's MP3 decoder, "hip". It is the default `Decoder` of every `Lame`
+/// context, but it can also be used on its own to decode streams that were
+/// not encoded by it.
+///
+/// Like `Lame`, a decoder is `Send` but not `Sync`. Decoders on different
+/// threads do not decode in parallel, as hip is not reentrant.
+pub struct HipDecoder {
+    ptr: HipPtr,
+}
+
+// SAFETY: The decoder is only accessed through its owner, and every call
+// into hip holds `HIP_LOCK`.
+unsafe impl Send for HipDecoder {}
+
+impl HipDecoder {
+    /// Creates a new decoder.
+    ///
+    /// Returns None if liblame could not allocate its internal structures.
+    pub fn new() -> Option<HipDecoder> {
+        let ptr = {
+            let _lock = lock_global(&HIP_LOCK);
+            unsafe { ffi::hip_decode_init() }
+        };
+        if ptr.is_null() {
+            return None;
+        }
+
+        Some(HipDecoder {
+            ptr,
+        })
+    }
+
+    /// Decodes MP3 data like `decode`, and also reports the parameters of the
+    /// decoded stream together with the encoder delay and padding stored in
+    /// its LAME tag.
+    ///
+    /// Returns `DecodeError::OutputBufferTooSmall` if a decoded frame does not
+    /// fit into the PCM buffers.
+    pub fn decode_with_info(&mut self, mp3buffer: &[u8], pcm_buffer_l: &mut [i16], pcm_buffer_r: &mut [i16]) -> Result<DecodeInfo, DecodeError> {
+        self.decode_into(mp3buffer, Output::Stereo(pcm_buffer_l, pcm_buffer_r), to_i16)
+    }
+
+    /// Decodes at most one frame into `frame_l` and `frame_r` and returns
+    /// the number of samples per channel, which is 0 if hip needs more data.
+    /// `mp3buffer` is appended to hip's internal buffer, so the buffered data
+    /// is drained by calling this again with an empty slice.
+    ///
+    /// Mono frames are written to both channels. `info` is updated with what
+    /// hip has learned about the stream so far.
+    pub(crate) fn decode_frame(&mut self, mp3buffer: &[u8],
+                               frame_l: &mut [i16; MAX_FRAME_SAMPLES], frame_r: &mut [i16; MAX_FRAME_SAMPLES],
+                               info: &mut DecodeInfo) -> Result<usize, DecodeError> {
+        let mut input = mp3buffer;
+        for _ in 0..=MAX_EMPTY_RESULTS {
+            let len = self.decode1(input, frame_l, frame_r, info)?;
+            if len > 0 {
+                return Ok(len);
+            }
+            input = &[];
+        }
+        Ok(0)
+    }
+
+    /// Makes a single call to hip, which may return no samples even though it
+    /// still holds data, see `MAX_EMPTY_RESULTS`.
+    fn decode1(&mut self, mp3buffer: &[u8],
+               frame_l: &mut [i16; MAX_FRAME_SAMPLES], frame_r: &mut [i16; MAX_FRAME_SAMPLES],
+               info: &mut DecodeInfo) -> Result<usize, DecodeError> {
+        let mut mp3data = ffi::mp3data_struct::default();
+        let mut enc_delay: c_int = -1;
+        let mut enc_padding: c_int = -1;
+        let retn = {
+            let _lock = lock_global(&HIP_LOCK);
+            unsafe {
+                ffi::hip_decode1_headersB(self.ptr,
+                                          mp3buffer.as_ptr(), mp3buffer.len(),
+                                          frame_l.as_mut_ptr(), frame_r.as_mut_ptr(),
+                                          &mut mp3data, &mut enc_delay, &mut enc_padding)
+            }
+        };
+        if retn < 0 { return Err(DecodeError::Unknown(retn)); }
+
+        if mp3data.header_parsed == 1 {
+            info.stream = Some(stream_info(&mp3data));
+        }
+        if enc_delay >= 0 {
+            info.encoder_delay = Some(enc_delay as usize);
+        }
+        if enc_padding >= 0 {
+            info.encoder_padding = Some(enc_padding as usize);
+        }
+
+        let len = retn as usize;
+        if mp3data.stereo == 1 {
+            frame_r[..len].copy_from_slice(&frame_l[..len]);
+        }
+        Ok(len)
+    }
+
+    fn decode_into<S: Sample>(&mut self, mp3buffer: &[u8], mut output: Output<S>, convert: fn(i16) -> S) -> Result<DecodeInfo, DecodeError> {
+        let mut info = DecodeInfo::default();
+        let mut frame_l = [0_i16; MAX_FRAME_SAMPLES];
+        let mut frame_r = [0_i16; MAX_FRAME_SAMPLES];
+        let mut converted_l = [convert(0); MAX_FRAME_SAMPLES];
+        let mut converted_r = [convert(0); MAX_FRAME_SAMPLES];
+
+        // hip buffers the whole input on the first call and returns at most
+        // one frame per call, so keep calling with no new data until it is
+        // drained.
+        let mut input = mp3buffer;
+        loop {
+            let len = self.decode_frame(input, &mut frame_l, &mut frame_r, &mut info)?;
+            input = &[];
+            if len == 0 { break; }
+
+            for (output, &sample) in converted_l.iter_mut().zip(&frame_l[..len]) {
+                *output = convert(sample);
+            }
+            for (output, &sample) in converted_r.iter_mut().zip(&frame_r[..len]) {
+                *output = convert(sample);
+            }
+            let mono = info.stream.is_some_and(|stream| stream.channels == 1);
+            output.write(info.samples, &converted_l[..len], &converted_r[..len], mono)?;
+            info.samples += len;
+        }
+
+        Ok(info)
+    }
+
+    /// Like `decode_frame`, but writes unclipped samples scaled to the 16-bit
+    /// range, and also returns whether the frame is mono.
+    #[cfg(hip_unclipped)]
+    fn decode_frame_unclipped(&mut self, mp3buffer: &[u8],
+                              frame_l: &mut [f32; MAX_FRAME_SAMPLES], frame_r: &mut [f32; MAX_FRAME_SAMPLES]) -> Result<(usize, bool), DecodeError> {
+        let mut input = mp3buffer;
+        for _ in 0..=MAX_EMPTY_RESULTS {
+            // The unclipped decoder does not report the header, but it leaves
+            // the right channel untouched for mono frames.
+            frame_r[0] = f32::NAN;
+            let retn = {
+                let _lock = lock_global(&HIP_LOCK);
+                unsafe {
+                    ffi::hip_decode1_unclipped(self.ptr,
+                                               input.as_ptr(), input.len(),
+                                               frame_l.as_mut_ptr(), frame_r.as_mut_ptr())
+                }
+            };
+            if retn < 0 { return Err(DecodeError::Unknown(retn)); }
+            if retn > 0 {
+                return Ok((retn as usize, frame_r[0].is_nan()));
+            }
+            input = &[];
+        }
+        Ok((0, false))
+    }
+
+    #[cfg(hip_unclipped)]
+    fn decode_f32(&mut self, mp3buffer: &[u8], mut output: Output<f32>) -> Result<usize, DecodeError> {
+        const SCALE: f32 = 1.0 / 32768.0;
+        let mut decoded_samples = 0;
+        let mut frame_l = [0_f32; MAX_FRAME_SAMPLES];
+        let mut frame_r = [0_f32; MAX_FRAME_SAMPLES];
+
+        // see `decode_into`
+        let mut input = mp3buffer;
+        loop {
+            let (len, mono) = self.decode_frame_unclipped(input, &mut frame_l, &mut frame_r)?;
+            input = &[];
+            if len == 0 { break; }
+
+            for sample in frame_l[..len].iter_mut().chain(frame_r[..len].iter_mut()) {
+                *sample *= SCALE;
+            }
+            output.write(decoded_samples, &frame_l[..len], &frame_r[..len], mono)?;
+            decoded_samples += len;
+        }
+
+        Ok(decoded_samples)
+    }
+
+    #[cfg(not(hip_unclipped))]
+    fn decode_f32(&mut self, mp3buffer: &[u8], output: Output<f32>) -> Result<usize, DecodeError> {
+        let info = self.decode_into(mp3buffer, output, to_f32)?;
+        Ok(info.samples)
+    }
+}
+
+impl Drop for HipDecoder {
+    fn drop(&mut self) {
+        unsafe { ffi::hip_decode_exit(self.ptr) };
+    }
+}
+
+impl Decode<i16> for HipDecoder {
+    fn decode(&mut self, mp3buffer: &[u8], pcm_buffer_l: &mut [i16], pcm_buffer_r: &mut [i16]) -> Result<usize, DecodeError> {
+        let info = self.decode_into(mp3buffer, Output::Stereo(pcm_buffer_l, pcm_buffer_r), to_i16)?;
+        Ok(info.samples)
+    }
+
+    fn decode_mono(&mut self, mp3buffer: &[u8], pcm_buffer: &mut [i16]) -> Result<usize, DecodeError> {
+        let info = self.decode_into(mp3buffer, Output::Mono(pcm_buffer), to_i16)?;
+        Ok(info.samples)
+    }
+}
+
+impl Decode<f32> for HipDecoder {
+    /// Decodes MP3 data into floating point samples. With the prebuilt or
+    /// vendored library the samples are not clipped, so peaks above full
+    /// scale created by the codec are kept. A system libmp3lame only exports
+    /// hip's 16-bit output, so there the samples are clipped.
+    fn decode(&mut self, mp3buffer: &[u8], pcm_buffer_l: &mut [f32], pcm_buffer_r: &mut [f32]) -> Result<usize, DecodeError> {
+        self.decode_f32(mp3buffer, Output::Stereo(pcm_buffer_l, pcm_buffer_r))
+    }
+
+    fn decode_mono(&mut self, mp3buffer: &[u8], pcm_buffer: &mut [f32]) -> Result<usize, DecodeError> {
+        self.decode_f32(mp3buffer, Output::Mono(pcm_buffer))
+    }
+}
@@ -0,0 +1,278 @@
+use super::{Decode, DecodeError, DecodeInfo, Output, Sample, StreamInfo, MAX_FRAME_SAMPLES};
+use crate::mpeg::MpegVersion;
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::sync::{Arc, Mutex};
+
+const MPEG1_KILOBITRATES: [usize; 14] = [32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320];
+const MPEG2_KILOBITRATES: [usize; 14] = [8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+
+/// Fields of a Layer III frame header.
+#[derive(Copy, Clone)]
+struct FrameHeader {
+    version: MpegVersion,
+    kilobitrate: usize,
+    sample_rate: usize,
+    padding: bool,
+    /// Whether a 16-bit CRC follows the header.
+    crc: bool,
+    mode: u8,
+    mode_ext: u8,
+}
+
+impl FrameHeader {
+    /// Parses the four header bytes at the start of `data`. Returns None if
+    /// they are not the header of a Layer III frame with a fixed bitrate.
+    fn parse(data: &[u8]) -> Option<FrameHeader> {
+        if data.len() < 4 || data[0] != 0xFF || data[1] & 0xE0 != 0xE0 {
+            return None;
+        }
+        let version = match (data[1] >> 3) & 3 {
+            3 => MpegVersion::Mpeg1,
+            2 => MpegVersion::Mpeg2,
+            0 => MpegVersion::Mpeg25,
+            _ => return None,
+        };
+        let layer3 = (data[1] >> 1) & 3 == 1;
+        let bitrate_index = (data[2] >> 4) as usize;
+        let sample_rate_index = ((data[2] >> 2) & 3) as usize;
+        // index 0 is free format, 15 and 3 are invalid
+        if !layer3 || bitrate_index == 0 || bitrate_index == 15 || sample_rate_index == 3 {
+            return None;
+        }
+        let kilobitrates = match version {
+            MpegVersion::Mpeg1 => &MPEG1_KILOBITRATES,
+            _ => &MPEG2_KILOBITRATES,
+        };
+        Some(FrameHeader {
+            version,
+            kilobitrate: kilobitrates[bitrate_index - 1],
+            sample_rate: version.sample_rates()[sample_rate_index] as usize,
+            padding: (data[2] >> 1) & 1 == 1,
+            // the protection bit is cleared when a CRC is present
+            crc: data[1] & 1 == 0,
+            mode: data[3] >> 6,
+            mode_ext: (data[3] >> 4) & 3,
+        })
+    }
+
+    fn samples(&self) -> usize {
+        match self.version {
+            MpegVersion::Mpeg1 => 1152,
+            _ => 576,
+        }
+    }
+
+    fn mono(&self) -> bool {
+        self.mode == 3
+    }
+
+    /// Length of the frame in bytes, including the header.
+    fn frame_length(&self) -> usize {
+        self.samples() / 8 * self.kilobitrate * 1000 / self.sample_rate + self.padding as usize
+    }
+
+    /// Whether the frame holds a Xing/Info tag instead of audio.
+    fn is_info_frame(&self, frame: &[u8]) -> bool {
+        let side_info = match (self.version, self.mono()) {
+            (MpegVersion::Mpeg1, true) => 17,
+            (MpegVersion::Mpeg1, false) => 32,
+            (_, true) => 9,
+            (_, false) => 17,
+        };
+        let is_tag = |offset: usize| {
+            let tag = &frame[offset.min(frame.len())..];
+            tag.starts_with(b"Xing") || tag.starts_with(b"Info")
+        };
+        // LAME writes the tag where it would be without a CRC, but other
+        // encoders may put it after the CRC
+        is_tag(4 + side_info) || (self.crc && is_tag(4 + 2 + side_info))
+    }
+
+    fn stream_info(&self) -> StreamInfo {
+        StreamInfo {
+            channels: if self.mono() { 1 } else { 2 },
+            sample_rate: self.sample_rate as u32,
+            kilobitrate: self.kilobitrate as u32,
+            mode: self.mode,
+            mode_ext: self.mode_ext,
+            frame_size: self.samples(),
+            total_frames: None,
+            total_samples: None,
+        }
+    }
+}
+
+/// Complete frames waiting to be decoded. puremp3 pulls them through `Read`.
+#[derive(Clone, Default)]
+struct FrameQueue(Arc<Mutex<VecDeque<u8>>>);
+
+impl Read for FrameQueue {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().read(buf)
+    }
+}
+
+fn to_i16(sample: f32) -> i16 {
+    (sample * 32768.0).round().clamp(-32768.0, 32767.0) as i16
+}
+
+fn to_f32(sample: f32) -> f32 {
+    sample
+}
+
+/// Pure Rust MP3 decoder backed by the `puremp3` crate.
+///
+/// Only Layer III streams with a fixed bitrate per frame are supported, so
+/// free format streams cannot be decoded. Xing/Info frames are skipped like
+/// hip does, but the encoder delay and padding are not read.
+pub struct PureMp3Decoder {
+    decoder: puremp3::Mp3Decoder<FrameQueue>,
+    queue: FrameQueue,
+    /// Headers of the frames in `queue`.
+    queued_headers: VecDeque<FrameHeader>,
+    /// Input that does not form a complete frame yet.
+    pending: Vec<u8>,
+}
+
+impl Default for PureMp3Decoder {
+    fn default() -> Self {
+        PureMp3Decoder::new()
+    }
+}
+
+impl PureMp3Decoder {
+    /// Creates a new decoder.
+    pub fn new() -> PureMp3Decoder {
+        let queue = FrameQueue::default();
+        PureMp3Decoder {
+            decoder: puremp3::Mp3Decoder::new(queue.clone()),
+            queue,
+            queued_headers: VecDeque::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// See `Decoder::decode_with_info`.
+    pub fn decode_with_info(&mut self, mp3buffer: &[u8], pcm_buffer_l: &mut [i16], pcm_buffer_r: &mut [i16]) -> Result<DecodeInfo, DecodeError> {
+        self.decode_into(mp3buffer, Output::Stereo(pcm_buffer_l, pcm_buffer_r), to_i16)
+    }
+
+    /// Decodes at most one frame, see `HipDecoder::decode_frame`.
+    pub(crate) fn decode_frame(&mut self, mp3buffer: &[u8],
+                               frame_l: &mut [i16; MAX_FRAME_SAMPLES], frame_r: &mut [i16; MAX_FRAME_SAMPLES],
+                               info: &mut DecodeInfo) -> Result<usize, DecodeError> {
+        self.push(mp3buffer);
+        let (header, frame) = match self.next_frame()? {
+            Some(frame) => frame,
+            None => return Ok(0),
+        };
+        info.stream = Some(header.stream_info());
+
+        let len = frame.num_samples;
+        let right = if header.mono() { 0 } else { 1 };
+        for i in 0..len {
+            frame_l[i] = to_i16(frame.samples[0][i]);
+            frame_r[i] = to_i16(frame.samples[right][i]);
+        }
+        Ok(len)
+    }
+
+    /// Appends `mp3buffer` to the pending input and moves every complete
+    /// frame to the queue.
+    fn push(&mut self, mp3buffer: &[u8]) {
+        self.pending.extend_from_slice(mp3buffer);
+
+        let mut start = 0;
+        let mut queue = self.queue.0.lock().unwrap();
+        while self.pending.len() - start >= 10 {
+            let data = &self.pending[start..];
+            // skip ID3v2 tags, whose size is stored as a 28 bit syncsafe integer
+            if data.starts_with(b"ID3") {
+                let size = data[6..10].iter().fold(0, |size, &byte| (size << 7) | (byte & 0x7F) as usize);
+                if data.len() < 10 + size {
+                    break;
+                }
+                start += 10 + size;
+                continue;
+            }
+            let header = match FrameHeader::parse(data) {
+                Some(header) => header,
+                // not in sync with the frames
+                None => {
+                    start += 1;
+                    continue;
+                }
+            };
+            let frame_length = header.frame_length();
+            if data.len() < frame_length {
+                break;
+            }
+            if !header.is_info_frame(&data[..frame_length]) {
+                queue.extend(&data[..frame_length]);
+                self.queued_headers.push_back(header);
+            }
+            start += frame_length;
+        }
+        self.pending.drain(..start);
+    }
+
+    /// Decodes the next queued frame, or returns None if no complete frame
+    /// has been received.
+    fn next_frame(&mut self) -> Result<Option<(FrameHeader, puremp3::Frame)>, DecodeError> {
+        let header = match self.queued_headers.pop_front() {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        let frame = self.decoder.next_frame().map_err(|_| DecodeError::InvalidFrame)?;
+        Ok(Some((header, frame)))
+    }
+
+    fn decode_into<S: Sample>(&mut self, mp3buffer: &[u8], mut output: Output<S>, convert: fn(f32) -> S) -> Result<DecodeInfo, DecodeError> {
+        let mut info = DecodeInfo::default();
+        let mut frame_l = [convert(0.0); MAX_FRAME_SAMPLES];
+        let mut frame_r = [convert(0.0); MAX_FRAME_SAMPLES];
+
+        self.push(mp3buffer);
+        while let Some((header, frame)) = self.next_frame()? {
+            info.stream = Some(header.stream_info());
+            let len = frame.num_samples;
+            for (output, &sample) in frame_l.iter_mut().zip(&frame.samples[0][..len]) {
+                *output = convert(sample);
+            }
+            for (output, &sample) in frame_r.iter_mut().zip(&frame.samples[1][..len]) {
+                *output = convert(sample);
+            }
+            output.write(info.samples, &frame_l[..len], &frame_r[..len], header.mono())?;
+            info.samples += len;
+        }
+
+        Ok(info)
+    }
+}
+
+impl Decode<i16> for PureMp3Decoder {
+    fn decode(&mut self, mp3buffer: &[u8], pcm_buffer_l: &mut [i16], pcm_buffer_r: &mut [i16]) -> Result<usize, DecodeError> {
+        let info = self.decode_into(mp3buffer, Output::Stereo(pcm_buffer_l, pcm_buffer_r), to_i16)?;
+        Ok(info.samples)
+    }
+
+    fn decode_mono(&mut self, mp3buffer: &[u8], pcm_buffer: &mut [i16]) -> Result<usize, DecodeError> {
+        let info = self.decode_into(mp3buffer, Output::Mono(pcm_buffer), to_i16)?;
+        Ok(info.samples)
+    }
+}
+
+impl Decode<f32> for PureMp3Decoder {
+    /// Decodes MP3 data into floating point samples. Like hip's float path,
+    /// the samples are not clipped.
+    fn decode(&mut self, mp3buffer: &[u8], pcm_buffer_l: &mut [f32], pcm_buffer_r: &mut [f32]) -> Result<usize, DecodeError> {
+        let info = self.decode_into(mp3buffer, Output::Stereo(pcm_buffer_l, pcm_buffer_r), to_f32)?;
+        Ok(info.samples)
+    }
+
+    fn decode_mono(&mut self, mp3buffer: &[u8], pcm_buffer: &mut [f32]) -> Result<usize, DecodeError> {
+        let info = self.decode_into(mp3buffer, Output::Mono(pcm_buffer), to_f32)?;
+        Ok(info.samples)
+    }
+}
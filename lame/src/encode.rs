@@ -1,8 +1,15 @@
 use crate::ffi;
+use crate::ffi::LamePtr;
 use crate::Lame;
+use std::borrow::Borrow;
+use std::fmt;
 use std::os::raw::c_int;
+use std::ptr;
+use std::slice;
 
 
+/// Error returned by LAME while encoding. Every variant corresponds to one
+/// of LAME's error codes, see `EncodeError::code`.
 #[derive(Debug)]
 pub enum EncodeError {
     OutputBufferTooSmall,
@@ -12,8 +19,36 @@ pub enum EncodeError {
     Unknown(c_int),
 }
 
+impl EncodeError {
+    /// LAME's error code.
+    pub fn code(&self) -> c_int {
+        match self {
+            EncodeError::OutputBufferTooSmall => -1,
+            EncodeError::NoMem => -2,
+            EncodeError::InitParamsNotCalled => -3,
+            EncodeError::PsychoAcousticError => -4,
+            EncodeError::Unknown(code) => *code,
+        }
+    }
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            EncodeError::OutputBufferTooSmall => "MP3 output buffer too small",
+            EncodeError::NoMem => "out of memory",
+            EncodeError::InitParamsNotCalled => "init_params was not called",
+            EncodeError::PsychoAcousticError => "psychoacoustic model error",
+            EncodeError::Unknown(_) => "unknown error",
+        };
+        write!(f, "{} (LAME error {})", message, self.code())
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
 fn handle_encode_error(retn: c_int) -> Result<usize, EncodeError> {
-    match retn.into() {
+    match retn {
         -1 => Err(EncodeError::OutputBufferTooSmall),
         -2 => Err(EncodeError::NoMem),
         -3 => Err(EncodeError::InitParamsNotCalled),
@@ -28,6 +63,34 @@ fn handle_encode_error(retn: c_int) -> Result<usize, EncodeError> {
     }
 }
 
+type PlanarEncodeFn<S> = unsafe extern "C" fn(LamePtr, *const S, *const S, c_int, *mut u8, c_int) -> c_int;
+type InterleavedEncodeFn<S> = unsafe extern "C" fn(LamePtr, *const S, c_int, *mut u8, c_int) -> c_int;
+
+/// Size in bytes that is always enough for the frames written by `flush`.
+pub const FLUSH_BUFFER_SIZE: usize = 7200;
+
+/// Returns the worst-case MP3 buffer size in bytes for encoding
+/// `num_samples` samples per channel, following LAME's recommendation of
+/// 1.25 * num_samples + 7200.
+///
+/// This assumes no resampling, see `Lame::max_encoded_size` for an encoder
+/// whose output sample rate is higher than its input sample rate.
+pub fn max_encoded_size(num_samples: usize) -> usize {
+    num_samples + num_samples.div_ceil(4) + FLUSH_BUFFER_SIZE
+}
+
+/// Grows `mp3_buffer` by `max_len` bytes, lets `encode` write into the new
+/// space and truncates it to the bytes actually written.
+fn append_with<F>(mp3_buffer: &mut Vec<u8>, max_len: usize, encode: F) -> Result<usize, EncodeError>
+    where F: FnOnce(&mut [u8]) -> Result<usize, EncodeError> {
+    let start = mp3_buffer.len();
+    mp3_buffer.resize(start + max_len, 0);
+    let result = encode(&mut mp3_buffer[start..]);
+    let written = *result.as_ref().unwrap_or(&0);
+    mp3_buffer.truncate(start + written);
+    result
+}
+
 fn int_size(sz: usize) -> c_int {
     if sz > c_int::MAX as usize {
         panic!("converting to c_int would overflow");
@@ -39,11 +102,55 @@ fn int_size(sz: usize) -> c_int {
 pub trait Encode<S> {
     /// Encodes PCM data into MP3 frames. The `pcm_left` and `pcm_right`
     /// buffers must be of the same length, or this function will panic.
+    ///
+    /// If the encoder is configured for one channel, `pcm_right` is ignored.
     fn encode(&mut self, pcm_left: &[S], pcm_right: &[S], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError>;
 
+    /// Encodes single channel PCM data into MP3 frames. The encoder must be
+    /// configured with `set_channels(1)`, or this function will panic.
+    fn encode_mono(&mut self, pcm: &[S], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError>;
+
+    /// Encodes interleaved PCM data into MP3 frames. For a stereo encoder
+    /// `pcm` holds left and right samples alternately and must be of even
+    /// length, or this function will panic. For a mono encoder this is the
+    /// same as `encode_mono`.
+    fn encode_interleaved(&mut self, pcm: &[S], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError>;
+
     fn flush(&mut self, mp3_buffer: &mut [u8]) -> Result<usize, EncodeError>;
     fn flush_nogap(&mut self, mp3_buffer: &mut [u8]) -> Result<usize, EncodeError>;
 
+    /// Encodes PCM data like `encode`, but appends the MP3 frames to
+    /// `mp3_buffer`, growing it as needed. Returns the number of bytes
+    /// appended.
+    fn encode_to_vec(&mut self, pcm_left: &[S], pcm_right: &[S], mp3_buffer: &mut Vec<u8>) -> Result<usize, EncodeError>
+        where Self: Borrow<Lame>
+    {
+        let max_len = Borrow::<Lame>::borrow(self).max_encoded_size(pcm_left.len());
+        append_with(mp3_buffer, max_len, |buffer| {
+            self.encode(pcm_left, pcm_right, buffer)
+        })
+    }
+
+    /// Like `encode_to_vec`, for single channel PCM data.
+    fn encode_mono_to_vec(&mut self, pcm: &[S], mp3_buffer: &mut Vec<u8>) -> Result<usize, EncodeError>
+        where Self: Borrow<Lame>
+    {
+        let max_len = Borrow::<Lame>::borrow(self).max_encoded_size(pcm.len());
+        append_with(mp3_buffer, max_len, |buffer| {
+            self.encode_mono(pcm, buffer)
+        })
+    }
+
+    /// Like `encode_to_vec`, for interleaved PCM data.
+    fn encode_interleaved_to_vec(&mut self, pcm: &[S], mp3_buffer: &mut Vec<u8>) -> Result<usize, EncodeError>
+        where Self: Borrow<Lame>
+    {
+        let max_len = Borrow::<Lame>::borrow(self).max_encoded_size(pcm.len());
+        append_with(mp3_buffer, max_len, |buffer| {
+            self.encode_interleaved(pcm, buffer)
+        })
+    }
+
     fn encode_flushing(&mut self, pcm_left: &[S], pcm_right: &[S], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
         let encoded_len = self.encode(pcm_left, pcm_right, mp3_buffer)?;
         let tail_buffer = &mut mp3_buffer[encoded_len..];
@@ -58,58 +165,244 @@ pub trait Encode<S> {
     }
 }
 
-impl Encode<i16> for Lame {
-    fn encode(&mut self, pcm_left: &[i16], pcm_right: &[i16], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+impl Lame {
+    /// Returns the worst-case MP3 buffer size in bytes for encoding
+    /// `num_samples` samples per channel with this encoder. When LAME
+    /// upsamples, the sample count is scaled to the output sample rate
+    /// before applying `max_encoded_size`.
+    pub fn max_encoded_size(&self, num_samples: usize) -> usize {
+        let in_rate = self.sample_rate() as usize;
+        let out_rate = self.out_sample_rate() as usize;
+        if in_rate == 0 || out_rate <= in_rate {
+            return max_encoded_size(num_samples);
+        }
+        max_encoded_size((num_samples * out_rate).div_ceil(in_rate))
+    }
+
+    fn encode_planar<S>(&mut self, encode_fn: PlanarEncodeFn<S>,
+                        pcm_left: &[S], pcm_right: &[S], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
         if pcm_left.len() != pcm_right.len() {
             panic!("left and right channels must have same number of samples!");
         }
-        let retn = unsafe {
-            ffi::lame_encode_buffer(self.ptr,
-                                    pcm_left.as_ptr(), pcm_right.as_ptr(), int_size(pcm_left.len()),
-                                    mp3_buffer.as_mut_ptr(), int_size(mp3_buffer.len()))
-        };
+        let _lock = self.hip_lock();
+        let retn = self.reporting(|ptr| unsafe {
+            encode_fn(ptr,
+                      pcm_left.as_ptr(), pcm_right.as_ptr(), int_size(pcm_left.len()),
+                      mp3_buffer.as_mut_ptr(), int_size(mp3_buffer.len()))
+        });
         handle_encode_error(retn)
     }
 
-    fn flush(&mut self, mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
-        let retn = unsafe {
-            ffi::lame_encode_flush(self.ptr, mp3_buffer.as_mut_ptr(), int_size(mp3_buffer.len()))
-        };
+    fn encode_mono_with<S>(&mut self, encode_fn: PlanarEncodeFn<S>,
+                           pcm: &[S], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        if self.channels() != 1 {
+            panic!("encode_mono requires an encoder configured for one channel!");
+        }
+        let _lock = self.hip_lock();
+        let retn = self.reporting(|ptr| unsafe {
+            encode_fn(ptr,
+                      pcm.as_ptr(), ptr::null(), int_size(pcm.len()),
+                      mp3_buffer.as_mut_ptr(), int_size(mp3_buffer.len()))
+        });
         handle_encode_error(retn)
     }
 
-    fn flush_nogap(&mut self, mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
-        let retn = unsafe {
-            ffi::lame_encode_flush_nogap(self.ptr, mp3_buffer.as_mut_ptr(), int_size(mp3_buffer.len()))
-        };
+    fn encode_interleaved_with<S>(&mut self, encode_fn: InterleavedEncodeFn<S>, mono_encode_fn: PlanarEncodeFn<S>,
+                                  pcm: &[S], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        if self.channels() == 1 {
+            return self.encode_mono_with(mono_encode_fn, pcm, mp3_buffer);
+        }
+        if pcm.len() % 2 == 1 {
+            panic!("interleaved stereo buffer must have an even number of samples!");
+        }
+        let _lock = self.hip_lock();
+        let retn = self.reporting(|ptr| unsafe {
+            encode_fn(ptr,
+                      pcm.as_ptr(), int_size(pcm.len() / 2),
+                      mp3_buffer.as_mut_ptr(), int_size(mp3_buffer.len()))
+        });
+        handle_encode_error(retn)
+    }
+
+    /// Flushes the remaining buffered samples into the final MP3 frames. This
+    /// does not depend on the sample type, so it is also available without
+    /// naming an `Encode` implementation.
+    pub fn flush(&mut self, mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        let _lock = self.hip_lock();
+        let retn = self.reporting(|ptr| unsafe {
+            ffi::lame_encode_flush(ptr, mp3_buffer.as_mut_ptr(), int_size(mp3_buffer.len()))
+        });
         handle_encode_error(retn)
     }
+
+    /// Like `flush`, but keeps the encoder state so that the next stream can
+    /// be encoded without a gap.
+    pub fn flush_nogap(&mut self, mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        let _lock = self.hip_lock();
+        let retn = self.reporting(|ptr| unsafe {
+            ffi::lame_encode_flush_nogap(ptr, mp3_buffer.as_mut_ptr(), int_size(mp3_buffer.len()))
+        });
+        handle_encode_error(retn)
+    }
+
+    /// Like `flush`, but appends the final frames to `mp3_buffer`, growing it
+    /// as needed. Returns the number of bytes appended.
+    pub fn flush_to_vec(&mut self, mp3_buffer: &mut Vec<u8>) -> Result<usize, EncodeError> {
+        append_with(mp3_buffer, FLUSH_BUFFER_SIZE, |buffer| self.flush(buffer))
+    }
+
+    /// Like `flush_nogap`, but appends the final frames to `mp3_buffer`,
+    /// growing it as needed. Returns the number of bytes appended.
+    pub fn flush_nogap_to_vec(&mut self, mp3_buffer: &mut Vec<u8>) -> Result<usize, EncodeError> {
+        append_with(mp3_buffer, FLUSH_BUFFER_SIZE, |buffer| self.flush_nogap(buffer))
+    }
 }
 
+impl Encode<i16> for Lame {
+    fn encode(&mut self, pcm_left: &[i16], pcm_right: &[i16], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        self.encode_planar(ffi::lame_encode_buffer, pcm_left, pcm_right, mp3_buffer)
+    }
+
+    fn encode_mono(&mut self, pcm: &[i16], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        self.encode_mono_with(ffi::lame_encode_buffer, pcm, mp3_buffer)
+    }
+
+    fn encode_interleaved(&mut self, pcm: &[i16], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        self.encode_interleaved_with(ffi::lame_encode_buffer_interleaved, ffi::lame_encode_buffer, pcm, mp3_buffer)
+    }
+
+    fn flush(&mut self, mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        Lame::flush(self, mp3_buffer)
+    }
+
+    fn flush_nogap(&mut self, mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        Lame::flush_nogap(self, mp3_buffer)
+    }
+}
+
+/// Samples are expected in the range -1.0 to 1.0.
 impl Encode<f32> for Lame {
     fn encode(&mut self, pcm_left: &[f32], pcm_right: &[f32], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
-        if pcm_left.len() != pcm_right.len() {
-            panic!("left and right channels must have same number of samples!");
+        self.encode_planar(ffi::lame_encode_buffer_ieee_float, pcm_left, pcm_right, mp3_buffer)
+    }
+
+    fn encode_mono(&mut self, pcm: &[f32], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        self.encode_mono_with(ffi::lame_encode_buffer_ieee_float, pcm, mp3_buffer)
+    }
+
+    fn encode_interleaved(&mut self, pcm: &[f32], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        self.encode_interleaved_with(ffi::lame_encode_buffer_interleaved_ieee_float, ffi::lame_encode_buffer_ieee_float, pcm, mp3_buffer)
+    }
+
+    fn flush(&mut self, mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        Lame::flush(self, mp3_buffer)
+    }
+
+    fn flush_nogap(&mut self, mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        Lame::flush_nogap(self, mp3_buffer)
+    }
+}
+
+/// Samples use the full range of `i32`.
+impl Encode<i32> for Lame {
+    fn encode(&mut self, pcm_left: &[i32], pcm_right: &[i32], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        self.encode_planar(ffi::lame_encode_buffer_int, pcm_left, pcm_right, mp3_buffer)
+    }
+
+    fn encode_mono(&mut self, pcm: &[i32], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        self.encode_mono_with(ffi::lame_encode_buffer_int, pcm, mp3_buffer)
+    }
+
+    fn encode_interleaved(&mut self, pcm: &[i32], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        self.encode_interleaved_with(ffi::lame_encode_buffer_interleaved_int, ffi::lame_encode_buffer_int, pcm, mp3_buffer)
+    }
+
+    fn flush(&mut self, mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        Lame::flush(self, mp3_buffer)
+    }
+
+    fn flush_nogap(&mut self, mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        Lame::flush_nogap(self, mp3_buffer)
+    }
+}
+
+/// Samples are expected in the range -1.0 to 1.0.
+impl Encode<f64> for Lame {
+    fn encode(&mut self, pcm_left: &[f64], pcm_right: &[f64], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        self.encode_planar(ffi::lame_encode_buffer_ieee_double, pcm_left, pcm_right, mp3_buffer)
+    }
+
+    fn encode_mono(&mut self, pcm: &[f64], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        self.encode_mono_with(ffi::lame_encode_buffer_ieee_double, pcm, mp3_buffer)
+    }
+
+    fn encode_interleaved(&mut self, pcm: &[f64], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        self.encode_interleaved_with(ffi::lame_encode_buffer_interleaved_ieee_double, ffi::lame_encode_buffer_ieee_double, pcm, mp3_buffer)
+    }
+
+    fn flush(&mut self, mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        Lame::flush(self, mp3_buffer)
+    }
+
+    fn flush_nogap(&mut self, mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        Lame::flush_nogap(self, mp3_buffer)
+    }
+}
+
+/// A packed little-endian 24-bit PCM sample, as stored in 24-bit WAV files.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct I24(pub [u8; 3]);
+
+impl I24 {
+    /// Reinterprets packed little-endian 24-bit PCM data as samples. The
+    /// length of `bytes` must be a multiple of 3, or this function will panic.
+    pub fn from_bytes(bytes: &[u8]) -> &[I24] {
+        let samples = bytes.len() / 3;
+        if samples * 3 != bytes.len() {
+            panic!("packed 24-bit data must be a multiple of 3 bytes long!");
         }
-        let retn = unsafe {
-            ffi::lame_encode_buffer_ieee_float(self.ptr,
-                                               pcm_left.as_ptr(), pcm_right.as_ptr(), int_size(pcm_left.len()),
-                                               mp3_buffer.as_mut_ptr(), int_size(mp3_buffer.len()))
-        };
-        handle_encode_error(retn)
+        // I24 is a transparent wrapper around [u8; 3], so it has the same
+        // size and alignment.
+        unsafe { slice::from_raw_parts(bytes.as_ptr() as *const I24, samples) }
+    }
+
+    /// Returns the sample scaled to the full range of `i32`.
+    pub fn to_i32(self) -> i32 {
+        i32::from_le_bytes([0, self.0[0], self.0[1], self.0[2]])
+    }
+}
+
+impl From<I24> for i32 {
+    fn from(sample: I24) -> i32 {
+        sample.to_i32()
+    }
+}
+
+fn widen(pcm: &[I24]) -> Vec<i32> {
+    pcm.iter().map(|&sample| sample.to_i32()).collect()
+}
+
+/// Samples are widened to `i32` in a temporary buffer before encoding, so no
+/// precision is lost.
+impl Encode<I24> for Lame {
+    fn encode(&mut self, pcm_left: &[I24], pcm_right: &[I24], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        Encode::<i32>::encode(self, &widen(pcm_left), &widen(pcm_right), mp3_buffer)
+    }
+
+    fn encode_mono(&mut self, pcm: &[I24], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        Encode::<i32>::encode_mono(self, &widen(pcm), mp3_buffer)
+    }
+
+    fn encode_interleaved(&mut self, pcm: &[I24], mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        Encode::<i32>::encode_interleaved(self, &widen(pcm), mp3_buffer)
     }
 
     fn flush(&mut self, mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
-        let retn = unsafe {
-            ffi::lame_encode_flush(self.ptr, mp3_buffer.as_mut_ptr(), int_size(mp3_buffer.len()))
-        };
-        handle_encode_error(retn)
+        Lame::flush(self, mp3_buffer)
     }
 
     fn flush_nogap(&mut self, mp3_buffer: &mut [u8]) -> Result<usize, EncodeError> {
-        let retn = unsafe {
-            ffi::lame_encode_flush_nogap(self.ptr, mp3_buffer.as_mut_ptr(), int_size(mp3_buffer.len()))
-        };
-        handle_encode_error(retn)
+        Lame::flush_nogap(self, mp3_buffer)
     }
-}
\ No newline at end of file
+}
@@ -1,8 +1,18 @@
-use std::os::raw::{c_int, c_void, c_ulong, c_short};
+use std::os::raw::{c_char, c_int, c_ulong, c_void};
+#[cfg(feature = "hip")]
+use std::os::raw::c_short;
 
 pub type LamePtr = *mut c_void;
+#[cfg(feature = "hip")]
 pub type HipPtr = *mut c_void;
 
+// `va_list` is passed as a pointer on the supported targets: it is a pointer
+// type on Windows, macOS and 32-bit x86, an array type on x86_64 Linux, and a
+// large struct passed by reference on aarch64 Linux.
+pub type VaList = *mut c_void;
+pub type ReportFn = unsafe extern "C" fn(format: *const c_char, args: VaList);
+
+#[cfg(feature = "hip")]
 #[repr(C)]
 #[derive(Copy, Clone)]
 #[derive(Debug)]
@@ -20,32 +30,145 @@ pub struct mp3data_struct {
     pub framenum: c_int,
 }
 
-#[link(name="mp3lame")]
+// linked by build.rs
 extern "C" {
+    pub fn get_lame_version() -> *const c_char;
+    pub fn get_lame_short_version() -> *const c_char;
+    pub fn get_psy_version() -> *const c_char;
+    pub fn get_lame_url() -> *const c_char;
+    pub fn lame_get_bitrate(mpeg_version: c_int, table_index: c_int) -> c_int;
+    pub fn lame_get_samplerate(mpeg_version: c_int, table_index: c_int) -> c_int;
     pub fn lame_init() -> LamePtr;
     pub fn lame_close(ptr: LamePtr) -> c_int;
     pub fn lame_set_in_samplerate(ptr: LamePtr, samplerate: c_int) -> c_int;
     pub fn lame_get_in_samplerate(ptr: LamePtr) -> c_int;
+    pub fn lame_set_out_samplerate(ptr: LamePtr, samplerate: c_int) -> c_int;
+    pub fn lame_get_out_samplerate(ptr: LamePtr) -> c_int;
     pub fn lame_set_num_channels(ptr: LamePtr, channels: c_int) -> c_int;
     pub fn lame_get_num_channels(ptr: LamePtr) -> c_int;
+    pub fn lame_set_num_samples(ptr: LamePtr, num_samples: c_ulong) -> c_int;
+    pub fn lame_get_num_samples(ptr: LamePtr) -> c_ulong;
+    pub fn lame_set_mode(ptr: LamePtr, mode: c_int) -> c_int;
+    pub fn lame_get_mode(ptr: LamePtr) -> c_int;
+    pub fn lame_set_force_ms(ptr: LamePtr, force_ms: c_int) -> c_int;
+    pub fn lame_get_force_ms(ptr: LamePtr) -> c_int;
     pub fn lame_set_quality(ptr: LamePtr, quality: c_int) -> c_int;
     pub fn lame_get_quality(ptr: LamePtr) -> c_int;
     pub fn lame_set_brate(ptr: LamePtr, quality: c_int) -> c_int;
     pub fn lame_get_brate(ptr: LamePtr) -> c_int;
+    pub fn lame_set_preset(ptr: LamePtr, preset: c_int) -> c_int;
+    pub fn lame_set_lowpassfreq(ptr: LamePtr, freq: c_int) -> c_int;
+    pub fn lame_get_lowpassfreq(ptr: LamePtr) -> c_int;
+    pub fn lame_set_lowpasswidth(ptr: LamePtr, width: c_int) -> c_int;
+    pub fn lame_get_lowpasswidth(ptr: LamePtr) -> c_int;
+    pub fn lame_set_highpassfreq(ptr: LamePtr, freq: c_int) -> c_int;
+    pub fn lame_get_highpassfreq(ptr: LamePtr) -> c_int;
+    pub fn lame_set_highpasswidth(ptr: LamePtr, width: c_int) -> c_int;
+    pub fn lame_get_highpasswidth(ptr: LamePtr) -> c_int;
+    pub fn lame_set_VBR(ptr: LamePtr, vbr_mode: c_int) -> c_int;
+    pub fn lame_get_VBR(ptr: LamePtr) -> c_int;
+    pub fn lame_set_VBR_q(ptr: LamePtr, quality: c_int) -> c_int;
+    pub fn lame_get_VBR_q(ptr: LamePtr) -> c_int;
+    pub fn lame_set_VBR_mean_bitrate_kbps(ptr: LamePtr, kbps: c_int) -> c_int;
+    pub fn lame_get_VBR_mean_bitrate_kbps(ptr: LamePtr) -> c_int;
+    pub fn lame_set_VBR_min_bitrate_kbps(ptr: LamePtr, kbps: c_int) -> c_int;
+    pub fn lame_get_VBR_min_bitrate_kbps(ptr: LamePtr) -> c_int;
+    pub fn lame_set_VBR_max_bitrate_kbps(ptr: LamePtr, kbps: c_int) -> c_int;
+    pub fn lame_get_VBR_max_bitrate_kbps(ptr: LamePtr) -> c_int;
+    pub fn lame_set_VBR_hard_min(ptr: LamePtr, hard_min: c_int) -> c_int;
+    pub fn lame_get_VBR_hard_min(ptr: LamePtr) -> c_int;
+    pub fn lame_set_bWriteVbrTag(ptr: LamePtr, write_tag: c_int) -> c_int;
+    pub fn lame_get_bWriteVbrTag(ptr: LamePtr) -> c_int;
+    pub fn lame_set_error_protection(ptr: LamePtr, error_protection: c_int) -> c_int;
+    pub fn lame_get_error_protection(ptr: LamePtr) -> c_int;
+    pub fn lame_set_copyright(ptr: LamePtr, copyright: c_int) -> c_int;
+    pub fn lame_get_copyright(ptr: LamePtr) -> c_int;
+    pub fn lame_set_original(ptr: LamePtr, original: c_int) -> c_int;
+    pub fn lame_get_original(ptr: LamePtr) -> c_int;
+    pub fn lame_set_emphasis(ptr: LamePtr, emphasis: c_int) -> c_int;
+    pub fn lame_get_emphasis(ptr: LamePtr) -> c_int;
+    pub fn lame_set_strict_ISO(ptr: LamePtr, strict_iso: c_int) -> c_int;
+    pub fn lame_get_strict_ISO(ptr: LamePtr) -> c_int;
+    pub fn lame_set_extension(ptr: LamePtr, extension: c_int) -> c_int;
+    pub fn lame_get_extension(ptr: LamePtr) -> c_int;
+    pub fn lame_set_free_format(ptr: LamePtr, free_format: c_int) -> c_int;
+    pub fn lame_get_free_format(ptr: LamePtr) -> c_int;
+    pub fn lame_set_findReplayGain(ptr: LamePtr, find_replay_gain: c_int) -> c_int;
+    pub fn lame_get_findReplayGain(ptr: LamePtr) -> c_int;
+    #[cfg(feature = "hip")]
+    pub fn lame_set_decode_on_the_fly(ptr: LamePtr, decode_on_the_fly: c_int) -> c_int;
+    pub fn lame_get_decode_on_the_fly(ptr: LamePtr) -> c_int;
+    pub fn lame_get_RadioGain(ptr: LamePtr) -> c_int;
+    #[cfg(feature = "hip")]
+    pub fn lame_get_PeakSample(ptr: LamePtr) -> f32;
+    #[cfg(feature = "hip")]
+    pub fn lame_get_noclipGainChange(ptr: LamePtr) -> c_int;
+    #[cfg(feature = "hip")]
+    pub fn lame_get_noclipScale(ptr: LamePtr) -> f32;
+    pub fn lame_set_errorf(ptr: LamePtr, func: Option<ReportFn>) -> c_int;
+    pub fn lame_set_debugf(ptr: LamePtr, func: Option<ReportFn>) -> c_int;
+    pub fn lame_set_msgf(ptr: LamePtr, func: Option<ReportFn>) -> c_int;
     pub fn lame_init_params(ptr: LamePtr) -> c_int;
+    pub fn lame_get_version(ptr: LamePtr) -> c_int;
+    pub fn lame_get_frameNum(ptr: LamePtr) -> c_int;
+    pub fn lame_get_totalframes(ptr: LamePtr) -> c_int;
+    pub fn lame_bitrate_kbps(ptr: LamePtr, bitrate_kbps: *mut c_int);
+    pub fn lame_bitrate_hist(ptr: LamePtr, bitrate_count: *mut c_int);
+    pub fn lame_stereo_mode_hist(ptr: LamePtr, stereo_mode_count: *mut c_int);
+    pub fn lame_bitrate_stereo_mode_hist(ptr: LamePtr, bitrate_stmode_count: *mut [c_int; 4]);
+    pub fn lame_block_type_hist(ptr: LamePtr, btype_count: *mut c_int);
     pub fn lame_encode_buffer(ptr: LamePtr,
                               pcm_l: *const i16, pcm_r: *const i16, pcm_numsamples: c_int,
                               mp3buf: *mut u8, mp3buf_size: c_int) -> c_int;
     pub fn lame_encode_buffer_ieee_float(ptr: LamePtr,
                                     pcm_l: *const f32, pcm_r: *const f32, pcm_numsamples: c_int,
                                     mp3buf: *mut u8, mp3buf_size: c_int) -> c_int;
+    pub fn lame_encode_buffer_int(ptr: LamePtr,
+                                  pcm_l: *const i32, pcm_r: *const i32, pcm_numsamples: c_int,
+                                  mp3buf: *mut u8, mp3buf_size: c_int) -> c_int;
+    pub fn lame_encode_buffer_ieee_double(ptr: LamePtr,
+                                          pcm_l: *const f64, pcm_r: *const f64, pcm_numsamples: c_int,
+                                          mp3buf: *mut u8, mp3buf_size: c_int) -> c_int;
+    pub fn lame_encode_buffer_interleaved(ptr: LamePtr,
+                                          pcm: *const i16, pcm_numsamples: c_int,
+                                          mp3buf: *mut u8, mp3buf_size: c_int) -> c_int;
+    pub fn lame_encode_buffer_interleaved_ieee_float(ptr: LamePtr,
+                                                     pcm: *const f32, pcm_numsamples: c_int,
+                                                     mp3buf: *mut u8, mp3buf_size: c_int) -> c_int;
+    pub fn lame_encode_buffer_interleaved_int(ptr: LamePtr,
+                                              pcm: *const i32, pcm_numsamples: c_int,
+                                              mp3buf: *mut u8, mp3buf_size: c_int) -> c_int;
+    pub fn lame_encode_buffer_interleaved_ieee_double(ptr: LamePtr,
+                                                      pcm: *const f64, pcm_numsamples: c_int,
+                                                      mp3buf: *mut u8, mp3buf_size: c_int) -> c_int;
     pub fn lame_encode_flush(ptr: LamePtr,
                              mp3buf: *mut u8, mp3buf_size: c_int) -> c_int;
     pub fn lame_encode_flush_nogap(ptr: LamePtr,
                                    mp3buf: *mut u8, mp3buf_size: c_int) -> c_int;
+    pub fn lame_get_lametag_frame(ptr: LamePtr, buffer: *mut u8, size: usize) -> usize;
+    pub fn lame_get_id3v2_tag(ptr: LamePtr, buffer: *mut u8, size: usize) -> usize;
     pub fn lame_get_encoder_delay(ptr: LamePtr) -> c_int;
     pub fn lame_get_encoder_padding(ptr: LamePtr) -> c_int;
 
+    pub fn id3tag_init(ptr: LamePtr);
+    pub fn id3tag_add_v2(ptr: LamePtr);
+    pub fn id3tag_v1_only(ptr: LamePtr);
+    pub fn id3tag_v2_only(ptr: LamePtr);
+    pub fn id3tag_set_title(ptr: LamePtr, title: *const c_char);
+    pub fn id3tag_set_artist(ptr: LamePtr, artist: *const c_char);
+    pub fn id3tag_set_album(ptr: LamePtr, album: *const c_char);
+    pub fn id3tag_set_year(ptr: LamePtr, year: *const c_char);
+    pub fn id3tag_set_comment(ptr: LamePtr, comment: *const c_char);
+    pub fn id3tag_set_track(ptr: LamePtr, track: *const c_char) -> c_int;
+    pub fn id3tag_set_genre(ptr: LamePtr, genre: *const c_char) -> c_int;
+    pub fn id3tag_set_albumart(ptr: LamePtr, image: *const c_char, size: usize) -> c_int;
+    pub fn id3tag_set_textinfo_utf16(ptr: LamePtr, id: *const c_char, text: *const u16) -> c_int;
+    pub fn id3tag_set_comment_utf16(ptr: LamePtr, lang: *const c_char, desc: *const u16, text: *const u16) -> c_int;
+}
+
+// the hip decoder
+#[cfg(feature = "hip")]
+extern "C" {
     pub fn hip_decode_init() -> HipPtr;
     pub fn hip_decode_exit(ptr: HipPtr) -> c_int;
     pub fn hip_decode1_headersB(ptr: HipPtr,
@@ -56,14 +179,31 @@ extern "C" {
                                 mp3data: *mut mp3data_struct,
                                 enc_delay: *mut c_int,
                                 enc_padding: *mut c_int) -> c_int;
+    // hip_decode1_headersB is used instead of the two functions below
+    #[allow(dead_code)]
     pub fn hip_decode1(ptr: HipPtr,
                        mp3buf: *const u8,
                        mp3buf_size: usize,
                        pcm_l: *mut c_short,
                        pcm_r: *mut c_short) -> c_int;
+    #[allow(dead_code)]
     pub fn hip_decode(ptr: HipPtr,
                       mp3buf: *const u8,
                       mp3buf_size: usize,
                       pcm_l: *mut c_short,
                       pcm_r: *mut c_short) -> c_int;
-}
\ No newline at end of file
+    // Not declared in lame.h and not in libmp3lame.sym, so only the static
+    // libraries export it. Works like hip_decode1, but does not clip and
+    // writes floating point samples scaled to the 16-bit range.
+    #[cfg(hip_unclipped)]
+    pub fn hip_decode1_unclipped(ptr: HipPtr,
+                                 mp3buf: *const u8,
+                                 mp3buf_size: usize,
+                                 pcm_l: *mut f32,
+                                 pcm_r: *mut f32) -> c_int;
+}
+
+// from the C library
+extern "C" {
+    pub fn vsnprintf(buffer: *mut c_char, size: usize, format: *const c_char, args: VaList) -> c_int;
+}
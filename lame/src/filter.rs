@@ -0,0 +1,94 @@
+use crate::ffi;
+use crate::{handle_simple_error, Error, Lame, Uninitialized};
+use std::os::raw::c_int;
+
+/// Cutoff frequency of the lowpass or highpass filter.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Cutoff {
+    /// LAME chooses the frequency from the bitrate and sample rate. After
+    /// `init_params` the getters report the chosen frequency instead.
+    Auto,
+    /// The filter is disabled.
+    Off,
+    /// Cutoff frequency in Hz.
+    Frequency(u32),
+}
+
+impl Cutoff {
+    fn to_raw(self) -> c_int {
+        match self {
+            Cutoff::Auto => 0,
+            Cutoff::Off => -1,
+            Cutoff::Frequency(freq) => freq as c_int,
+        }
+    }
+
+    fn from_raw(freq: c_int) -> Cutoff {
+        match freq {
+            0 => Cutoff::Auto,
+            freq if freq < 0 => Cutoff::Off,
+            freq => Cutoff::Frequency(freq as u32),
+        }
+    }
+}
+
+// LAME uses -1 for the default transition width
+fn width_from_raw(width: c_int) -> Option<u32> {
+    if width < 0 { None } else { Some(width as u32) }
+}
+
+impl Lame<Uninitialized> {
+    /// Sets the lowpass filter, which limits the bandwidth of the encoded
+    /// stream. Defaults to `Cutoff::Auto`.
+    pub fn set_lowpass(&mut self, cutoff: Cutoff) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_lowpassfreq(self.ptr, cutoff.to_raw())
+        })
+    }
+
+    /// Sets the width of the lowpass transition band in Hz. Defaults to one
+    /// polyphase filter band.
+    pub fn set_lowpass_width(&mut self, width: u32) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_lowpasswidth(self.ptr, width as c_int)
+        })
+    }
+
+    /// Sets the highpass filter. Defaults to `Cutoff::Auto`, with which
+    /// LAME only filters at low bitrates.
+    pub fn set_highpass(&mut self, cutoff: Cutoff) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_highpassfreq(self.ptr, cutoff.to_raw())
+        })
+    }
+
+    /// Sets the width of the highpass transition band in Hz. Defaults to one
+    /// polyphase filter band.
+    pub fn set_highpass_width(&mut self, width: u32) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_highpasswidth(self.ptr, width as c_int)
+        })
+    }
+}
+
+impl<State> Lame<State> {
+    /// Lowpass filter cutoff.
+    pub fn lowpass(&self) -> Cutoff {
+        Cutoff::from_raw(unsafe { ffi::lame_get_lowpassfreq(self.ptr) })
+    }
+
+    /// Width of the lowpass transition band in Hz, or None for the default.
+    pub fn lowpass_width(&self) -> Option<u32> {
+        width_from_raw(unsafe { ffi::lame_get_lowpasswidth(self.ptr) })
+    }
+
+    /// Highpass filter cutoff.
+    pub fn highpass(&self) -> Cutoff {
+        Cutoff::from_raw(unsafe { ffi::lame_get_highpassfreq(self.ptr) })
+    }
+
+    /// Width of the highpass transition band in Hz, or None for the default.
+    pub fn highpass_width(&self) -> Option<u32> {
+        width_from_raw(unsafe { ffi::lame_get_highpasswidth(self.ptr) })
+    }
+}
@@ -0,0 +1,201 @@
+use crate::ffi;
+use crate::ffi::LamePtr;
+use crate::{Lame, Uninitialized};
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::os::raw::c_char;
+use std::ptr;
+
+#[derive(Debug)]
+pub enum Id3TagError {
+    /// A text field contains a NUL character, or characters outside Latin-1
+    /// in a field that can only be written as Latin-1.
+    InvalidString,
+    /// The genre number is out of range.
+    InvalidGenre,
+    /// The album art is not a JPEG, PNG or GIF image.
+    UnsupportedAlbumArt,
+}
+
+impl fmt::Display for Id3TagError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Id3TagError::InvalidString => write!(f, "tag text contains a NUL character or cannot be encoded"),
+            Id3TagError::InvalidGenre => write!(f, "genre number out of range"),
+            Id3TagError::UnsupportedAlbumArt => write!(f, "album art is not a JPEG, PNG or GIF image"),
+        }
+    }
+}
+
+impl std::error::Error for Id3TagError {}
+
+/// Which ID3 tag versions are written.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Id3Version {
+    /// An ID3v1 tag, plus an ID3v2 tag if the fields do not fit into ID3v1.
+    #[default]
+    Auto,
+    /// Both an ID3v1 and an ID3v2 tag.
+    Both,
+    /// Only an ID3v1 tag.
+    V1Only,
+    /// Only an ID3v2 tag.
+    V2Only,
+}
+
+/// Metadata written as ID3 tags by the encoder. Fields that are None are
+/// left empty.
+///
+/// Text is written as Latin-1 where possible. Other text is written as
+/// UTF-16 to the ID3v2 tag only, and the year and track number must be
+/// Latin-1.
+///
+/// The ID3v2 tag is written at the start of the stream by the first encode
+/// call, and the ID3v1 tag at the end of the stream by `flush`.
+#[derive(Debug, Clone, Default)]
+pub struct Id3Tag {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub year: Option<String>,
+    pub comment: Option<String>,
+    /// Track number, optionally followed by the total number of tracks as in
+    /// "3/12".
+    pub track: Option<String>,
+    /// Genre name, or ID3v1 genre number. Names that are not ID3v1 genres
+    /// are kept as-is in the ID3v2 tag.
+    pub genre: Option<String>,
+    /// Album art as the contents of a JPEG, PNG or GIF file. Only written to
+    /// the ID3v2 tag.
+    pub album_art: Option<Vec<u8>>,
+    pub version: Id3Version,
+}
+
+// Text converted for LAME, whose plain setters take Latin-1 and whose UTF-16
+// setters expect a byte order mark.
+enum Text {
+    Latin1(CString),
+    Utf16(Vec<u16>),
+}
+
+fn convert_text(text: &str) -> Result<Text, Id3TagError> {
+    if text.contains('\0') {
+        return Err(Id3TagError::InvalidString);
+    }
+    if text.chars().all(|c| (c as u32) <= 0xff) {
+        let latin1 = text.chars().map(|c| c as u8).collect::<Vec<u8>>();
+        let latin1 = CString::new(latin1).map_err(|_| Id3TagError::InvalidString)?;
+        return Ok(Text::Latin1(latin1));
+    }
+    let utf16 = std::iter::once(0xfeff)
+        .chain(text.encode_utf16())
+        .chain(std::iter::once(0))
+        .collect();
+    Ok(Text::Utf16(utf16))
+}
+
+fn latin1_text(text: &str) -> Result<CString, Id3TagError> {
+    match convert_text(text)? {
+        Text::Latin1(text) => Ok(text),
+        Text::Utf16(_) => Err(Id3TagError::InvalidString),
+    }
+}
+
+// Where text that is not Latin-1 goes in the ID3v2 tag.
+enum Utf16Frame {
+    TextInfo(&'static CStr),
+    Comment,
+}
+
+fn set_text(ptr: LamePtr, text: &Option<String>,
+            set_fn: unsafe extern "C" fn(LamePtr, *const c_char),
+            frame: Utf16Frame, v2: bool) -> Result<(), Id3TagError> {
+    let text = match text {
+        Some(text) => convert_text(text)?,
+        None => return Ok(()),
+    };
+    let retn = match text {
+        Text::Latin1(text) => {
+            unsafe { set_fn(ptr, text.as_ptr()) };
+            0
+        }
+        // ID3v1 has no way to store other encodings
+        Text::Utf16(_) if !v2 => return Err(Id3TagError::InvalidString),
+        Text::Utf16(text) => match frame {
+            Utf16Frame::TextInfo(id) => unsafe {
+                ffi::id3tag_set_textinfo_utf16(ptr, id.as_ptr(), text.as_ptr())
+            },
+            Utf16Frame::Comment => unsafe {
+                ffi::id3tag_set_comment_utf16(ptr, ptr::null(), ptr::null(), text.as_ptr())
+            },
+        },
+    };
+    if retn != 0 {
+        return Err(Id3TagError::InvalidString);
+    }
+    Ok(())
+}
+
+impl Lame<Uninitialized> {
+    /// Sets the ID3 tags written to the stream, replacing any tags set
+    /// before.
+    pub fn set_id3tag(&mut self, tag: &Id3Tag) -> Result<(), Id3TagError> {
+        unsafe { ffi::id3tag_init(self.ptr) };
+        match tag.version {
+            Id3Version::Auto => {}
+            Id3Version::Both => unsafe { ffi::id3tag_add_v2(self.ptr) },
+            Id3Version::V1Only => unsafe { ffi::id3tag_v1_only(self.ptr) },
+            Id3Version::V2Only => unsafe { ffi::id3tag_v2_only(self.ptr) },
+        }
+
+        let v2 = tag.version != Id3Version::V1Only;
+        set_text(self.ptr, &tag.title, ffi::id3tag_set_title, Utf16Frame::TextInfo(c"TIT2"), v2)?;
+        set_text(self.ptr, &tag.artist, ffi::id3tag_set_artist, Utf16Frame::TextInfo(c"TPE1"), v2)?;
+        set_text(self.ptr, &tag.album, ffi::id3tag_set_album, Utf16Frame::TextInfo(c"TALB"), v2)?;
+        set_text(self.ptr, &tag.comment, ffi::id3tag_set_comment, Utf16Frame::Comment, v2)?;
+
+        if let Some(year) = &tag.year {
+            let year = latin1_text(year)?;
+            unsafe { ffi::id3tag_set_year(self.ptr, year.as_ptr()) };
+        }
+        if let Some(track) = &tag.track {
+            let track = latin1_text(track)?;
+            // -1 only means that the number does not fit into ID3v1, it is
+            // still written to ID3v2
+            unsafe { ffi::id3tag_set_track(self.ptr, track.as_ptr()) };
+        }
+        if let Some(genre) = &tag.genre {
+            let retn = match convert_text(genre)? {
+                Text::Latin1(genre) => unsafe { ffi::id3tag_set_genre(self.ptr, genre.as_ptr()) },
+                Text::Utf16(_) if !v2 => return Err(Id3TagError::InvalidString),
+                Text::Utf16(genre) => unsafe {
+                    ffi::id3tag_set_textinfo_utf16(self.ptr, c"TCON".as_ptr(), genre.as_ptr())
+                },
+            };
+            // -2 means the name is not an ID3v1 genre, which is fine for ID3v2
+            match retn {
+                -1 => return Err(Id3TagError::InvalidGenre),
+                0 | -2 => {}
+                _ => return Err(Id3TagError::InvalidString),
+            }
+        }
+        if let Some(image) = &tag.album_art {
+            let retn = unsafe {
+                ffi::id3tag_set_albumart(self.ptr, image.as_ptr() as *const c_char, image.len())
+            };
+            if retn != 0 {
+                return Err(Id3TagError::UnsupportedAlbumArt);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<State> Lame<State> {
+    /// Size in bytes of the ID3v2 tag at the start of the stream, or 0 if
+    /// none is written.
+    pub fn id3v2_tag_size(&self) -> usize {
+        // called without a buffer, it returns the size of the tag
+        unsafe { ffi::lame_get_id3v2_tag(self.ptr, ptr::null_mut(), 0) }
+    }
+}
@@ -0,0 +1,53 @@
+use crate::ffi;
+use crate::mpeg::MpegVersion;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+
+// LAME returns pointers to static strings
+fn static_str(ptr: *const c_char) -> &'static str {
+    if ptr.is_null() {
+        return "";
+    }
+    unsafe { CStr::from_ptr(ptr) }.to_str().unwrap_or_default()
+}
+
+/// Full LAME version, e.g. "3.100".
+pub fn version() -> &'static str {
+    static_str(unsafe { ffi::get_lame_version() })
+}
+
+/// LAME version without alpha/beta details.
+pub fn short_version() -> &'static str {
+    static_str(unsafe { ffi::get_lame_short_version() })
+}
+
+/// Version of LAME's psychoacoustic model.
+pub fn psy_version() -> &'static str {
+    static_str(unsafe { ffi::get_psy_version() })
+}
+
+/// URL of the LAME project.
+pub fn url() -> &'static str {
+    static_str(unsafe { ffi::get_lame_url() })
+}
+
+/// Bitrates of LAME's frame header table for `version`, in kilobits per
+/// second. The encoder accepts fewer for MPEG-2.5, see
+/// `MpegVersion::kilobitrates`.
+pub fn kilobitrates(version: MpegVersion) -> Vec<u32> {
+    table(|index| unsafe { ffi::lame_get_bitrate(version.to_raw(), index) })
+}
+
+/// Sample rates of `version` in Hz.
+pub fn sample_rates(version: MpegVersion) -> Vec<u32> {
+    table(|index| unsafe { ffi::lame_get_samplerate(version.to_raw(), index) })
+}
+
+// Reads the positive entries of a LAME table. Index 0 of the bitrate table
+// is free format, and indices out of range return -1.
+fn table(entry: impl Fn(c_int) -> c_int) -> Vec<u32> {
+    (0..16).map(entry)
+        .filter(|&value| value > 0)
+        .map(|value| value as u32)
+        .collect()
+}
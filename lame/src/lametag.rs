@@ -0,0 +1,59 @@
+use crate::ffi;
+use crate::{handle_simple_error, Error, Initialized, Lame, Uninitialized};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::os::raw::c_int;
+use std::ptr;
+
+impl Lame<Uninitialized> {
+    /// Sets whether a LAME/Xing tag is written. Defaults to true.
+    ///
+    /// When enabled, the first frame of the stream is a placeholder which
+    /// should be overwritten by `Lame::lametag_frame` once encoding is done.
+    pub fn set_write_vbr_tag(&mut self, write_tag: bool) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_bWriteVbrTag(self.ptr, write_tag as c_int)
+        })
+    }
+}
+
+impl<State> Lame<State> {
+    /// Whether a LAME/Xing tag is written. See `set_write_vbr_tag`.
+    pub fn write_vbr_tag(&self) -> bool {
+        unsafe { ffi::lame_get_bWriteVbrTag(self.ptr) != 0 }
+    }
+}
+
+impl Lame<Initialized> {
+    /// Returns the LAME/Xing tag frame for the stream encoded so far. Call
+    /// it after `flush`, and write it over the placeholder frame at the
+    /// start of the stream.
+    ///
+    /// The tag holds the frame count and seek table needed to seek in VBR
+    /// files, and the encoder delay and padding needed for gapless playback.
+    /// Returns an empty vector if the tag is disabled.
+    pub fn lametag_frame(&self) -> Vec<u8> {
+        // called without a buffer, it returns the size of the frame
+        let size = unsafe { ffi::lame_get_lametag_frame(self.ptr, ptr::null_mut(), 0) };
+        let mut frame = vec![0_u8; size];
+        let written = unsafe { ffi::lame_get_lametag_frame(self.ptr, frame.as_mut_ptr(), frame.len()) };
+        frame.truncate(written);
+        frame
+    }
+
+    /// Writes `lametag_frame` over the placeholder frame of a stream that
+    /// starts at `stream_start` in `writer`. The placeholder follows the
+    /// ID3v2 tag, if one was written. The position of `writer` is restored
+    /// afterwards.
+    pub fn write_lametag<W: Write + Seek>(&self, writer: &mut W, stream_start: u64) -> io::Result<()> {
+        let frame = self.lametag_frame();
+        if frame.is_empty() {
+            return Ok(());
+        }
+
+        let position = writer.stream_position()?;
+        writer.seek(SeekFrom::Start(stream_start + self.id3v2_tag_size() as u64))?;
+        writer.write_all(&frame)?;
+        writer.seek(SeekFrom::Start(position))?;
+        writer.flush()
+    }
+}
@@ -1,15 +1,38 @@
 mod ffi;
 pub mod encode;
 pub mod decode;
+pub mod mpeg;
+pub mod info;
+pub mod vbr;
+pub mod preset;
+pub mod stereo;
+pub mod filter;
+pub mod bitstream;
+pub mod stats;
+pub mod replaygain;
+pub mod report;
+pub mod lametag;
+pub mod id3tag;
+pub mod reader;
+pub mod writer;
 
+use std::fmt;
 use std::ptr;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
 use std::ops::Drop;
-use std::os::raw::c_int;
-use crate::ffi::{LamePtr, HipPtr};
+use std::os::raw::{c_int, c_ulong};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use crate::decode::Decoder;
+use crate::ffi::LamePtr;
+use crate::mpeg::MpegVersion;
+use crate::report::ReportHandler;
+use crate::vbr::VbrMode;
 
+/// Error returned by LAME while configuring the encoder. Every variant
+/// corresponds to one of LAME's error codes, see `Error::code`.
 #[derive(Debug)]
 pub enum Error {
-    Ok,
     GenericError,
     NoMem,
     BadBitRate,
@@ -18,10 +41,23 @@ pub enum Error {
     Unknown(c_int),
 }
 
+impl Error {
+    /// LAME's error code.
+    pub fn code(&self) -> c_int {
+        match self {
+            Error::GenericError => -1,
+            Error::NoMem => -10,
+            Error::BadBitRate => -11,
+            Error::BadSampleFreq => -12,
+            Error::InternalError => -13,
+            Error::Unknown(code) => *code,
+        }
+    }
+}
+
 impl From<c_int> for Error {
     fn from(errcode: c_int) -> Error {
         match errcode {
-            0 => Error::Ok,
             -1 => Error::GenericError,
             -10 => Error::NoMem,
             -11 => Error::BadBitRate,
@@ -32,48 +68,100 @@ impl From<c_int> for Error {
     }
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            Error::GenericError => "generic error",
+            Error::NoMem => "out of memory",
+            Error::BadBitRate => "bitrate not supported",
+            Error::BadSampleFreq => "sample rate not supported",
+            Error::InternalError => "internal error",
+            Error::Unknown(_) => "unknown error",
+        };
+        write!(f, "{} (LAME error {})", message, self.code())
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Serializes `lame_init` and `lame_init_params`, which fill LAME's global
+/// lookup tables.
+static INIT_LOCK: Mutex<()> = Mutex::new(());
+
+/// Locks one of the mutexes guarding LAME's global state. They protect no
+/// data of their own, so a poisoned lock is still usable.
+pub(crate) fn lock_global(lock: &'static Mutex<()>) -> MutexGuard<'static, ()> {
+    lock.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
 fn handle_simple_error(retn: c_int) -> Result<(), Error> {
-    match retn.into() {
-        Error::Ok => Ok(()),
-        err => Err(err),
+    match retn {
+        0 => Ok(()),
+        err => Err(err.into()),
     }
 }
 
+/// Marker for a `Lame` context whose parameters can still be changed.
+pub struct Uninitialized;
+
+/// Marker for a `Lame` context whose parameters have been applied by
+/// `init_params`.
+pub struct Initialized;
+
 /// Represents a Lame encoder context.
-pub struct Lame {
+///
+/// A context starts out as `Lame<Uninitialized>`, which only allows setting
+/// parameters. `init_params` validates them and turns the context into a
+/// `Lame<Initialized>` (or simply `Lame`), which can encode but can no longer
+/// be reconfigured.
+///
+/// A context can be moved to another thread (`Send`), but not shared between
+/// threads (`!Sync`). Independent contexts can be used on different threads
+/// at the same time.
+pub struct Lame<State = Initialized> {
     ptr: LamePtr,
-    hip: HipPtr,
+    decoder: Decoder,
+    report: Option<Box<ReportHandler>>,
+    state: PhantomData<State>,
 }
 
-impl Lame {
+// SAFETY: The context is only accessed through `&mut self` or `&self` of its
+// owner and holds no thread-local state. LAME's global state is only written
+// by `lame_init`, `lame_init_params` and the hip decoder, which are
+// serialized by `INIT_LOCK` and `HIP_LOCK`. Encoding enters hip when decoding
+// on the fly, and then holds `HIP_LOCK` as well.
+unsafe impl<State> Send for Lame<State> {}
+
+impl Lame<Uninitialized> {
     /// Creates a new Lame encoder context with default parameters.
     ///
     /// Returns None if liblame could not allocate its internal structures.
-    pub fn new() -> Option<Lame> {
-        let ctx = unsafe { ffi::lame_init() };
+    pub fn new() -> Option<Lame<Uninitialized>> {
+        let ctx = {
+            let _lock = lock_global(&INIT_LOCK);
+            unsafe { ffi::lame_init() }
+        };
 
-        if ctx == ptr::null_mut() {
+        if ctx.is_null() {
             return None;
         }
 
-        let hip = unsafe { ffi::hip_decode_init() };
-        if hip == ptr::null_mut() {
-            unsafe { ffi::lame_close(ctx) };
-            return None;
-        }
+        let decoder = match Decoder::new() {
+            Some(decoder) => decoder,
+            None => {
+                unsafe { ffi::lame_close(ctx) };
+                return None;
+            }
+        };
 
         Some(Lame {
             ptr: ctx,
-            hip,
+            decoder,
+            report: None,
+            state: PhantomData,
         })
     }
 
-    // Encoder
-    /// Sample rate of input PCM data. Defaults to 44100 Hz.
-    pub fn sample_rate(&self) -> u32 {
-        unsafe { ffi::lame_get_in_samplerate(self.ptr) as u32 }
-    }
-
     /// Sets sample rate of input PCM data.
     pub fn set_sample_rate(&mut self, sample_rate: u32) -> Result<(), Error> {
         handle_simple_error(unsafe {
@@ -81,21 +169,37 @@ impl Lame {
         })
     }
 
-    /// Number of channels in input stream. Defaults to 2.
-    pub fn channels(&self) -> u8 {
-        unsafe { ffi::lame_get_num_channels(self.ptr) as u8 }
+    /// Sets sample rate of the encoded MP3 stream. LAME resamples the input
+    /// if it differs from the input sample rate. The rate also selects the
+    /// MPEG version, see `MpegVersion`.
+    ///
+    /// Defaults to 0, which lets LAME pick a rate suited to the bitrate.
+    pub fn set_out_sample_rate(&mut self, sample_rate: u32) -> Result<(), Error> {
+        if sample_rate != 0 && MpegVersion::from_sample_rate(sample_rate).is_none() {
+            return Err(Error::BadSampleFreq);
+        }
+        handle_simple_error(unsafe {
+            ffi::lame_set_out_samplerate(self.ptr, sample_rate as c_int)
+        })
     }
 
-    /// Sets number of channels in input stream.
+    /// Sets number of channels in input stream. With one channel the output
+    /// is encoded in mono mode; use `Encode::encode_mono` to feed it.
     pub fn set_channels(&mut self, channels: u8) -> Result<(), Error> {
         handle_simple_error(unsafe {
             ffi::lame_set_num_channels(self.ptr, channels as c_int)
         })
     }
 
-    /// LAME quality parameter. See `set_quality` for more details.
-    pub fn quality(&self) -> u8 {
-        unsafe { ffi::lame_get_quality(self.ptr) as u8 }
+    /// Sets the number of samples per channel that will be encoded, which
+    /// LAME uses to estimate the number of frames in the stream, see
+    /// `EncoderStats::total_frames`.
+    ///
+    /// Defaults to `u32::MAX`, which means the number is unknown.
+    pub fn set_num_samples(&mut self, num_samples: u32) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_num_samples(self.ptr, num_samples as c_ulong)
+        })
     }
 
     /// Sets LAME's quality parameter. True quality is determined by the
@@ -110,11 +214,6 @@ impl Lame {
         })
     }
 
-    /// Returns the output bitrate in kilobits per second.
-    pub fn kilobitrate(&self) -> i32 {
-        unsafe { ffi::lame_get_brate(self.ptr) as i32 }
-    }
-
     /// Sets the target output bitrate. This value is in kilobits per second,
     /// so passing 320 would select an output bitrate of 320kbps.
     pub fn set_kilobitrate(&mut self, quality: i32) -> Result<(), Error> {
@@ -124,12 +223,78 @@ impl Lame {
     }
 
     /// Sets more internal parameters according to the other basic parameter
-    /// settings.
-    pub fn init_params(&mut self) -> Result<(), Error> {
-        handle_simple_error(unsafe {
-            ffi::lame_init_params(self.ptr)
+    /// settings, and returns the context ready for encoding.
+    ///
+    /// If an output sample rate is set, a CBR bitrate that its MPEG version
+    /// does not support is rejected with `Error::BadBitRate` instead of being
+    /// replaced by the nearest allowed one. Free format streams may use any
+    /// bitrate, and without a bitrate LAME picks one for the rate.
+    ///
+    /// The context is released if LAME rejects the parameters.
+    pub fn init_params(mut self) -> Result<Lame<Initialized>, Error> {
+        if let Some(version) = MpegVersion::from_sample_rate(self.out_sample_rate()) {
+            // 0 lets LAME pick a bitrate suited to the sample rate
+            let kilobitrate = self.kilobitrate() as u32;
+            if kilobitrate != 0 && self.vbr_mode() == VbrMode::Off && !self.free_format()
+                && !version.kilobitrates().contains(&kilobitrate) {
+                return Err(Error::BadBitRate);
+            }
+        }
+
+        // decoding on the fly makes LAME set up a hip decoder
+        let _hip_lock = self.hip_lock();
+        handle_simple_error(self.reporting(|ptr| {
+            let _lock = lock_global(&INIT_LOCK);
+            unsafe { ffi::lame_init_params(ptr) }
+        }))?;
+
+        // Lame implements Drop, so its fields have to be moved out of a
+        // ManuallyDrop to keep them alive.
+        let lame = ManuallyDrop::new(self);
+        Ok(Lame {
+            ptr: lame.ptr,
+            decoder: unsafe { ptr::read(&lame.decoder) },
+            report: unsafe { ptr::read(&lame.report) },
+            state: PhantomData,
         })
     }
+}
+
+impl<State> Lame<State> {
+    /// Sample rate of input PCM data. Defaults to 44100 Hz.
+    pub fn sample_rate(&self) -> u32 {
+        unsafe { ffi::lame_get_in_samplerate(self.ptr) as u32 }
+    }
+
+    /// Sample rate of the encoded MP3 stream. Before `init_params` this is
+    /// 0 unless set by `set_out_sample_rate`; afterwards it is the rate LAME
+    /// picked.
+    pub fn out_sample_rate(&self) -> u32 {
+        unsafe { ffi::lame_get_out_samplerate(self.ptr) as u32 }
+    }
+
+    /// Number of channels in input stream. Defaults to 2.
+    pub fn channels(&self) -> u8 {
+        unsafe { ffi::lame_get_num_channels(self.ptr) as u8 }
+    }
+
+    /// LAME quality parameter. See `set_quality` for more details.
+    pub fn quality(&self) -> u8 {
+        unsafe { ffi::lame_get_quality(self.ptr) as u8 }
+    }
+
+    /// Returns the output bitrate in kilobits per second.
+    pub fn kilobitrate(&self) -> i32 {
+        unsafe { ffi::lame_get_brate(self.ptr) as i32 }
+    }
+}
+
+impl Lame<Initialized> {
+    /// MPEG version of the encoded stream, determined by the output sample
+    /// rate.
+    pub fn mpeg_version(&self) -> MpegVersion {
+        MpegVersion::from_raw(unsafe { ffi::lame_get_version(self.ptr) })
+    }
 
     pub fn encoder_delay(&mut self) -> usize {
         let retn = unsafe { ffi::lame_get_encoder_delay(self.ptr) };
@@ -142,9 +307,8 @@ impl Lame {
     }
 }
 
-impl Drop for Lame {
+impl<State> Drop for Lame<State> {
     fn drop(&mut self) {
-        unsafe { ffi::hip_decode_exit(self.hip) };
         unsafe { ffi::lame_close(self.ptr) };
     }
 }
@@ -0,0 +1,66 @@
+use std::os::raw::c_int;
+
+/// MPEG audio version of an MP3 stream. It follows from the sample rate:
+/// MPEG-2 and MPEG-2.5 cover the lower rates with lower bitrates.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MpegVersion {
+    /// 32, 44.1 and 48 kHz.
+    Mpeg1,
+    /// 16, 22.05 and 24 kHz.
+    Mpeg2,
+    /// 8, 11.025 and 12 kHz.
+    Mpeg25,
+}
+
+impl MpegVersion {
+    /// Returns the MPEG version used for `sample_rate`, or None if MP3 does
+    /// not support that rate.
+    pub fn from_sample_rate(sample_rate: u32) -> Option<MpegVersion> {
+        match sample_rate {
+            32000 | 44100 | 48000 => Some(MpegVersion::Mpeg1),
+            16000 | 22050 | 24000 => Some(MpegVersion::Mpeg2),
+            8000 | 11025 | 12000 => Some(MpegVersion::Mpeg25),
+            _ => None,
+        }
+    }
+
+    /// Sample rates of this version in Hz.
+    pub fn sample_rates(self) -> &'static [u32] {
+        match self {
+            MpegVersion::Mpeg1 => &[44100, 48000, 32000],
+            MpegVersion::Mpeg2 => &[22050, 24000, 16000],
+            MpegVersion::Mpeg25 => &[11025, 12000, 8000],
+        }
+    }
+
+    /// Bitrates of this version allowed in CBR mode, in kilobits per second.
+    /// This is LAME's encoder limit, which for MPEG-2.5 stops at 64 kbps
+    /// although the frame header table returned by `info::kilobitrates` goes
+    /// up to 160 kbps.
+    pub fn kilobitrates(self) -> &'static [u32] {
+        match self {
+            MpegVersion::Mpeg1 => &[32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320],
+            MpegVersion::Mpeg2 => &[8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+            MpegVersion::Mpeg25 => &[8, 16, 24, 32, 40, 48, 56, 64],
+        }
+    }
+
+    /// Converts to LAME's version number.
+    pub(crate) fn to_raw(self) -> c_int {
+        match self {
+            MpegVersion::Mpeg2 => 0,
+            MpegVersion::Mpeg1 => 1,
+            MpegVersion::Mpeg25 => 2,
+        }
+    }
+
+    /// Converts LAME's version number (0 = MPEG-2, 1 = MPEG-1,
+    /// 2 = MPEG-2.5).
+    pub(crate) fn from_raw(version: c_int) -> MpegVersion {
+        match version {
+            0 => MpegVersion::Mpeg2,
+            2 => MpegVersion::Mpeg25,
+            _ => MpegVersion::Mpeg1,
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use crate::ffi;
+use crate::vbr::VbrMode;
+use crate::{Error, Lame, Uninitialized};
+use std::os::raw::c_int;
+
+/// LAME's built-in presets, as selected by the `-V` and `--preset` options
+/// of the `lame` command line tool.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Preset {
+    /// VBR quality 0, around 245 kbps.
+    V0,
+    V1,
+    /// VBR quality 2, around 190 kbps.
+    V2,
+    V3,
+    V4,
+    V5,
+    V6,
+    V7,
+    V8,
+    /// VBR quality 9, around 65 kbps.
+    V9,
+    /// ABR at the given mean bitrate, from 8 to 320 kbps.
+    Abr(u32),
+    /// 320 kbps CBR.
+    Insane,
+    /// Same as `V0`.
+    Extreme,
+    /// Same as `V2`.
+    Standard,
+    /// Same as `V4`.
+    Medium,
+}
+
+impl Preset {
+    fn to_raw(self) -> c_int {
+        match self {
+            Preset::V0 => 500,
+            Preset::V1 => 490,
+            Preset::V2 => 480,
+            Preset::V3 => 470,
+            Preset::V4 => 460,
+            Preset::V5 => 450,
+            Preset::V6 => 440,
+            Preset::V7 => 430,
+            Preset::V8 => 420,
+            Preset::V9 => 410,
+            Preset::Abr(kilobitrate) => kilobitrate as c_int,
+            Preset::Insane => 1003,
+            Preset::Extreme => 1002,
+            Preset::Standard => 1001,
+            Preset::Medium => 1006,
+        }
+    }
+
+    fn is_vbr(self) -> bool {
+        !matches!(self, Preset::Abr(_) | Preset::Insane)
+    }
+}
+
+impl Lame<Uninitialized> {
+    /// Applies a preset. This overrides the bitrate, VBR, quality and filter
+    /// settings made before, so call it first and adjust afterwards.
+    ///
+    /// The VBR presets keep a VBR mode set by `set_vbr_mode` and otherwise
+    /// switch to `VbrMode::Mtrh`. An ABR bitrate outside 8 to 320 kbps is
+    /// rejected with `Error::BadBitRate`.
+    pub fn set_preset(&mut self, preset: Preset) -> Result<(), Error> {
+        if let Preset::Abr(kilobitrate) = preset {
+            if !(8..=320).contains(&kilobitrate) {
+                return Err(Error::BadBitRate);
+            }
+        }
+        if preset.is_vbr() && self.vbr_mode() == VbrMode::Off {
+            self.set_vbr_mode(VbrMode::Mtrh)?;
+        }
+        // lame_set_preset returns the applied preset, not an error code
+        unsafe { ffi::lame_set_preset(self.ptr, preset.to_raw()) };
+        Ok(())
+    }
+}
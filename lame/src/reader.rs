@@ -0,0 +1,166 @@
+use crate::decode::{DecodeError, DecodeInfo, Decoder, StreamInfo, MAX_FRAME_SAMPLES};
+use std::error;
+use std::fmt;
+use std::io::{self, Read};
+
+/// Number of bytes read from the underlying reader at a time.
+const READ_SIZE: usize = 4096;
+
+#[derive(Debug)]
+pub enum ReadError {
+    Decode(DecodeError),
+    Io(io::Error),
+}
+
+impl From<DecodeError> for ReadError {
+    fn from(err: DecodeError) -> ReadError {
+        ReadError::Decode(err)
+    }
+}
+
+impl From<io::Error> for ReadError {
+    fn from(err: io::Error) -> ReadError {
+        ReadError::Io(err)
+    }
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::Decode(_) => write!(f, "MP3 decoding failed"),
+            ReadError::Io(_) => write!(f, "I/O error"),
+        }
+    }
+}
+
+impl error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ReadError::Decode(err) => Some(err),
+            ReadError::Io(err) => Some(err),
+        }
+    }
+}
+
+/// Streaming MP3 decoder. Reads MP3 data from the underlying reader as needed
+/// and yields the decoded PCM frames as `(left, right)` pairs. Mono streams
+/// yield the same sample on both channels.
+///
+/// Data is fed to the decoder in chunks, so frames may span reads.
+pub struct Mp3Reader<R: Read> {
+    inner: R,
+    decoder: Decoder,
+    input: Vec<u8>,
+    input_len: usize,
+    info: DecodeInfo,
+    frame_l: [i16; MAX_FRAME_SAMPLES],
+    frame_r: [i16; MAX_FRAME_SAMPLES],
+    frame_len: usize,
+    position: usize,
+    eof: bool,
+    finished: bool,
+}
+
+impl<R: Read> Mp3Reader<R> {
+    /// Creates a reader that decodes the MP3 stream read from `inner` with
+    /// the default decoder.
+    ///
+    /// Returns None if liblame could not allocate the decoder.
+    pub fn new(inner: R) -> Option<Mp3Reader<R>> {
+        Some(Mp3Reader::with_decoder(inner, Decoder::new()?))
+    }
+
+    /// Creates a reader that decodes the MP3 stream read from `inner` with
+    /// the given decoder.
+    pub fn with_decoder(inner: R, decoder: Decoder) -> Mp3Reader<R> {
+        Mp3Reader {
+            inner,
+            decoder,
+            input: vec![0; READ_SIZE],
+            input_len: 0,
+            info: DecodeInfo::default(),
+            frame_l: [0; MAX_FRAME_SAMPLES],
+            frame_r: [0; MAX_FRAME_SAMPLES],
+            frame_len: 0,
+            position: 0,
+            eof: false,
+            finished: false,
+        }
+    }
+
+    /// Parameters of the stream, or None if no frame header has been parsed
+    /// yet.
+    pub fn info(&self) -> Option<StreamInfo> {
+        self.info.stream
+    }
+
+    /// Samples of encoder delay at the start of the stream, if the LAME tag
+    /// was found.
+    pub fn encoder_delay(&self) -> Option<usize> {
+        self.info.encoder_delay
+    }
+
+    /// Samples of padding at the end of the stream, if the LAME tag was
+    /// found.
+    pub fn encoder_padding(&self) -> Option<usize> {
+        self.info.encoder_padding
+    }
+
+    /// The underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Decodes the next frame, reading more data as needed. Returns false at
+    /// the end of the stream, and `DecodeError::NoHeader` once if the stream
+    /// contained no MP3 frame at all.
+    fn next_frame(&mut self) -> Result<bool, ReadError> {
+        if self.finished {
+            return Ok(false);
+        }
+        loop {
+            // newly read data is passed once, then the decoder is drained
+            let input = &self.input[..self.input_len];
+            self.input_len = 0;
+            let len = self.decoder.decode_frame(input, &mut self.frame_l, &mut self.frame_r, &mut self.info)?;
+            if len > 0 {
+                self.frame_len = len;
+                self.position = 0;
+                return Ok(true);
+            }
+
+            if self.eof {
+                self.finished = true;
+                if self.info.stream.is_none() {
+                    return Err(DecodeError::NoHeader.into());
+                }
+                return Ok(false);
+            }
+            let read = match self.inner.read(&mut self.input) {
+                Ok(read) => read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            };
+            self.input_len = read;
+            self.eof = read == 0;
+        }
+    }
+}
+
+impl<R: Read> Iterator for Mp3Reader<R> {
+    type Item = Result<(i16, i16), ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position == self.frame_len {
+            match self.next_frame() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        let sample = (self.frame_l[self.position], self.frame_r[self.position]);
+        self.position += 1;
+        Some(Ok(sample))
+    }
+}
@@ -0,0 +1,108 @@
+use crate::ffi;
+use crate::{handle_simple_error, Error, Lame, Initialized, Uninitialized};
+use std::os::raw::c_int;
+use std::sync::MutexGuard;
+#[cfg(feature = "hip")]
+use crate::decode::HIP_LOCK;
+#[cfg(feature = "hip")]
+use crate::lock_global;
+
+/// Full scale of the decoded samples LAME searches for the peak.
+#[cfg(feature = "hip")]
+const PEAK_FULL_SCALE: f32 = 32767.0;
+
+impl Lame<Uninitialized> {
+    /// Analyzes the input while encoding to compute its ReplayGain, see
+    /// `radio_gain`. Defaults to false.
+    pub fn set_find_replay_gain(&mut self, find_replay_gain: bool) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_findReplayGain(self.ptr, find_replay_gain as c_int)
+        })
+    }
+
+    /// Decodes every encoded frame with hip to find the peak of the decoded
+    /// signal, see `peak_sample`. This slows down encoding considerably.
+    /// Defaults to false.
+    ///
+    /// While enabled, encoding holds the lock that serializes hip decoders,
+    /// so it does not run in parallel with decoding on other threads.
+    #[cfg(feature = "hip")]
+    pub fn set_decode_on_the_fly(&mut self, decode_on_the_fly: bool) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_decode_on_the_fly(self.ptr, decode_on_the_fly as c_int)
+        })
+    }
+}
+
+impl<State> Lame<State> {
+    /// Whether the ReplayGain of the input is computed.
+    pub fn find_replay_gain(&self) -> bool {
+        unsafe { ffi::lame_get_findReplayGain(self.ptr) != 0 }
+    }
+
+    /// Whether the encoded frames are decoded to find their peak.
+    pub fn decode_on_the_fly(&self) -> bool {
+        unsafe { ffi::lame_get_decode_on_the_fly(self.ptr) != 0 }
+    }
+
+    /// Holds `HIP_LOCK` while encoding calls into hip to decode on the fly.
+    #[cfg(feature = "hip")]
+    pub(crate) fn hip_lock(&self) -> Option<MutexGuard<'static, ()>> {
+        if self.decode_on_the_fly() {
+            Some(lock_global(&HIP_LOCK))
+        } else {
+            None
+        }
+    }
+
+    #[cfg(not(feature = "hip"))]
+    pub(crate) fn hip_lock(&self) -> Option<MutexGuard<'static, ()>> {
+        None
+    }
+}
+
+impl Lame<Initialized> {
+    /// ReplayGain of the samples encoded so far in dB, or None if
+    /// `set_find_replay_gain` was not enabled. The value is final after
+    /// `flush`.
+    pub fn radio_gain(&self) -> Option<f32> {
+        if !self.find_replay_gain() {
+            return None;
+        }
+        Some(unsafe { ffi::lame_get_RadioGain(self.ptr) } as f32 / 10.0)
+    }
+
+    /// Peak of the decoded signal, where 1.0 is full scale, or None if
+    /// `set_decode_on_the_fly` was not enabled. A peak above 1.0 clips when
+    /// decoded to integer samples.
+    #[cfg(feature = "hip")]
+    pub fn peak_sample(&self) -> Option<f32> {
+        if !self.decode_on_the_fly() {
+            return None;
+        }
+        Some(unsafe { ffi::lame_get_PeakSample(self.ptr) } / PEAK_FULL_SCALE)
+    }
+
+    /// Gain reduction in dB that keeps the decoded signal from clipping, or
+    /// None if `set_decode_on_the_fly` was not enabled. A negative value is
+    /// the headroom of a signal that does not clip.
+    #[cfg(feature = "hip")]
+    pub fn noclip_gain_change(&self) -> Option<f32> {
+        if !self.decode_on_the_fly() {
+            return None;
+        }
+        Some(unsafe { ffi::lame_get_noclipGainChange(self.ptr) } as f32 / 10.0)
+    }
+
+    /// Scale factor for the input that keeps the decoded signal from
+    /// clipping, or None if it does not clip or `set_decode_on_the_fly` was
+    /// not enabled.
+    #[cfg(feature = "hip")]
+    pub fn noclip_scale(&self) -> Option<f32> {
+        let scale = unsafe { ffi::lame_get_noclipScale(self.ptr) };
+        if !self.decode_on_the_fly() || scale <= 0.0 {
+            return None;
+        }
+        Some(scale)
+    }
+}
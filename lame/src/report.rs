@@ -0,0 +1,110 @@
+use crate::ffi;
+use crate::ffi::LamePtr;
+use crate::{handle_simple_error, Error, Lame, Uninitialized};
+use std::cell::Cell;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+/// Kind of a diagnostic reported by LAME.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReportLevel {
+    /// Errors and warnings, e.g. about parameters LAME had to change.
+    Error,
+    /// Debugging output.
+    Debug,
+    /// Informational messages.
+    Message,
+}
+
+pub(crate) type ReportHandler = dyn FnMut(ReportLevel, &str) + Send;
+
+/// Size of the buffer a report is formatted into. Longer reports are
+/// truncated, as the `va_list` can only be consumed once.
+const REPORT_BUFFER_SIZE: usize = 1024;
+
+thread_local! {
+    // LAME's report functions get no user data, so the handler of the
+    // context that is calling into LAME is looked up here.
+    static CURRENT_HANDLER: Cell<Option<*mut ReportHandler>> = const { Cell::new(None) };
+}
+
+/// Restores the previous handler when a call into LAME returns or unwinds.
+struct HandlerScope(Option<*mut ReportHandler>);
+
+impl Drop for HandlerScope {
+    fn drop(&mut self) {
+        CURRENT_HANDLER.with(|current| current.set(self.0));
+    }
+}
+
+unsafe fn report(level: ReportLevel, format: *const c_char, args: ffi::VaList) {
+    let handler = match CURRENT_HANDLER.with(Cell::get) {
+        Some(handler) => handler,
+        None => return,
+    };
+    let mut buffer = [0 as c_char; REPORT_BUFFER_SIZE];
+    if ffi::vsnprintf(buffer.as_mut_ptr(), buffer.len(), format, args) < 0 {
+        return;
+    }
+    let message = CStr::from_ptr(buffer.as_ptr()).to_string_lossy();
+    (*handler)(level, message.trim_end());
+}
+
+unsafe extern "C" fn report_error(format: *const c_char, args: ffi::VaList) {
+    report(ReportLevel::Error, format, args)
+}
+
+unsafe extern "C" fn report_debug(format: *const c_char, args: ffi::VaList) {
+    report(ReportLevel::Debug, format, args)
+}
+
+unsafe extern "C" fn report_message(format: *const c_char, args: ffi::VaList) {
+    report(ReportLevel::Message, format, args)
+}
+
+impl Lame<Uninitialized> {
+    /// Installs a handler for LAME's error, debug and informational
+    /// messages, which LAME otherwise prints to stderr.
+    ///
+    /// The handler is called on the thread that uses the context, during
+    /// `init_params`, encoding and flushing. It must not panic.
+    pub fn set_report_handler<F>(&mut self, handler: F) -> Result<(), Error>
+        where F: FnMut(ReportLevel, &str) + Send + 'static {
+        handle_simple_error(unsafe {
+            ffi::lame_set_errorf(self.ptr, Some(report_error))
+        })?;
+        handle_simple_error(unsafe {
+            ffi::lame_set_debugf(self.ptr, Some(report_debug))
+        })?;
+        handle_simple_error(unsafe {
+            ffi::lame_set_msgf(self.ptr, Some(report_message))
+        })?;
+        self.report = Some(Box::new(handler));
+        Ok(())
+    }
+
+    /// Forwards LAME's messages to the `log` facade with target "lame",
+    /// prefixed with `name` to tell contexts apart. Errors are logged at the
+    /// error level, debug output at the debug level and other messages at
+    /// the info level.
+    #[cfg(feature = "log")]
+    pub fn set_report_log(&mut self, name: &str) -> Result<(), Error> {
+        let name = name.to_string();
+        self.set_report_handler(move |level, message| match level {
+            ReportLevel::Error => log::error!(target: "lame", "{}: {}", name, message),
+            ReportLevel::Debug => log::debug!(target: "lame", "{}: {}", name, message),
+            ReportLevel::Message => log::info!(target: "lame", "{}: {}", name, message),
+        })
+    }
+}
+
+impl<State> Lame<State> {
+    /// Calls into LAME with this context's report handler receiving the
+    /// messages reported meanwhile.
+    pub(crate) fn reporting<T>(&mut self, f: impl FnOnce(LamePtr) -> T) -> T {
+        let ptr = self.ptr;
+        let handler = self.report.as_deref_mut().map(|handler| handler as *mut ReportHandler);
+        let _scope = HandlerScope(CURRENT_HANDLER.with(|current| current.replace(handler)));
+        f(ptr)
+    }
+}
@@ -0,0 +1,120 @@
+use crate::ffi;
+use crate::Lame;
+use std::os::raw::{c_int, c_ulong};
+
+/// Number of bitrates in LAME's histograms.
+const BITRATE_COUNT: usize = 14;
+
+/// Number of frames encoded with each stereo coding.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct StereoModeCount {
+    /// Left/right stereo.
+    pub left_right: u32,
+    /// Left/right stereo with intensity stereo.
+    pub left_right_intensity: u32,
+    /// Mid/side (joint) stereo.
+    pub mid_side: u32,
+    /// Mid/side stereo with intensity stereo.
+    pub mid_side_intensity: u32,
+}
+
+impl StereoModeCount {
+    fn from_raw(count: &[c_int; 4]) -> StereoModeCount {
+        StereoModeCount {
+            left_right: count[0] as u32,
+            left_right_intensity: count[1] as u32,
+            mid_side: count[2] as u32,
+            mid_side_intensity: count[3] as u32,
+        }
+    }
+}
+
+/// Number of frames encoded at one bitrate.
+#[derive(Debug, Copy, Clone)]
+pub struct BitrateCount {
+    /// Bitrate in kilobits per second.
+    pub kilobitrate: u32,
+    /// Number of frames encoded at this bitrate.
+    pub frames: u32,
+    /// Stereo coding of these frames.
+    pub stereo_modes: StereoModeCount,
+}
+
+/// Number of granules encoded with each MDCT block type.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct BlockTypeCount {
+    pub long: u32,
+    pub start: u32,
+    pub short: u32,
+    pub stop: u32,
+    pub mixed: u32,
+}
+
+/// Snapshot of the encoder statistics, see `Lame::stats`.
+#[derive(Debug, Clone)]
+pub struct EncoderStats {
+    /// Number of frames encoded so far.
+    pub frames: u32,
+    /// Estimated number of frames in the whole stream, or None if the
+    /// number of input samples was not set with `Lame::set_num_samples`.
+    pub total_frames: Option<u32>,
+    /// Frame count per bitrate, for every bitrate of the stream's MPEG
+    /// version. CBR streams only use one of them.
+    pub bitrates: Vec<BitrateCount>,
+    /// Frame count per stereo coding. All zero for mono streams.
+    pub stereo_modes: StereoModeCount,
+    /// Granule count per block type.
+    pub block_types: BlockTypeCount,
+}
+
+impl Lame {
+    /// Returns the statistics of the frames encoded so far.
+    pub fn stats(&self) -> EncoderStats {
+        let mut kbps: [c_int; BITRATE_COUNT] = [0; BITRATE_COUNT];
+        let mut bitrate_hist: [c_int; BITRATE_COUNT] = [0; BITRATE_COUNT];
+        let mut bitrate_stereo_hist: [[c_int; 4]; BITRATE_COUNT] = [[0; 4]; BITRATE_COUNT];
+        let mut stereo_hist: [c_int; 4] = [0; 4];
+        // the last entry is the sum of all block types
+        let mut block_hist: [c_int; 6] = [0; 6];
+        unsafe {
+            ffi::lame_bitrate_kbps(self.ptr, kbps.as_mut_ptr());
+            ffi::lame_bitrate_hist(self.ptr, bitrate_hist.as_mut_ptr());
+            ffi::lame_bitrate_stereo_mode_hist(self.ptr, bitrate_stereo_hist.as_mut_ptr());
+            ffi::lame_stereo_mode_hist(self.ptr, stereo_hist.as_mut_ptr());
+            ffi::lame_block_type_hist(self.ptr, block_hist.as_mut_ptr());
+        }
+
+        let bitrates = (0..BITRATE_COUNT)
+            // bitrates the MPEG version does not have are reported as -1
+            .filter(|&i| kbps[i] > 0)
+            .map(|i| BitrateCount {
+                kilobitrate: kbps[i] as u32,
+                frames: bitrate_hist[i] as u32,
+                stereo_modes: StereoModeCount::from_raw(&bitrate_stereo_hist[i]),
+            })
+            .collect();
+
+        // LAME only recognizes its "unknown" default where unsigned long is
+        // 32 bits wide, and estimates from 2^32 - 1 samples elsewhere
+        let num_samples = unsafe { ffi::lame_get_num_samples(self.ptr) };
+        let total_frames = if num_samples >= u32::MAX as c_ulong {
+            None
+        } else {
+            Some(unsafe { ffi::lame_get_totalframes(self.ptr) } as u32)
+        };
+
+        EncoderStats {
+            frames: unsafe { ffi::lame_get_frameNum(self.ptr) } as u32,
+            total_frames,
+            bitrates,
+            stereo_modes: StereoModeCount::from_raw(&stereo_hist),
+            block_types: BlockTypeCount {
+                long: block_hist[0] as u32,
+                start: block_hist[1] as u32,
+                short: block_hist[2] as u32,
+                stop: block_hist[3] as u32,
+                mixed: block_hist[4] as u32,
+            },
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use crate::ffi;
+use crate::{handle_simple_error, Error, Lame, Uninitialized};
+use std::os::raw::c_int;
+
+/// Channel mode of the encoded stream.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StereoMode {
+    /// Left and right channels are coded independently.
+    Stereo,
+    /// LAME chooses between left/right and mid/side coding for every frame.
+    JointStereo,
+    /// Joint stereo with mid/side coding used for every frame.
+    ForcedJointStereo,
+    /// Two independent mono channels. LAME encodes them like `Stereo`
+    /// except for the header flag.
+    DualChannel,
+    /// A single channel. Stereo input is mixed down.
+    Mono,
+}
+
+impl StereoMode {
+    fn to_raw(self) -> c_int {
+        match self {
+            StereoMode::Stereo => 0,
+            StereoMode::JointStereo | StereoMode::ForcedJointStereo => 1,
+            StereoMode::DualChannel => 2,
+            StereoMode::Mono => 3,
+        }
+    }
+
+    pub(crate) fn from_raw(mode: c_int, force_ms: bool) -> StereoMode {
+        match mode {
+            0 => StereoMode::Stereo,
+            1 if force_ms => StereoMode::ForcedJointStereo,
+            1 => StereoMode::JointStereo,
+            2 => StereoMode::DualChannel,
+            _ => StereoMode::Mono,
+        }
+    }
+}
+
+impl Lame<Uninitialized> {
+    /// Sets the channel mode. By default LAME picks one from the bitrate and
+    /// the number of input channels.
+    pub fn set_stereo_mode(&mut self, mode: StereoMode) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_mode(self.ptr, mode.to_raw())
+        })?;
+        let force_ms = (mode == StereoMode::ForcedJointStereo) as c_int;
+        handle_simple_error(unsafe {
+            ffi::lame_set_force_ms(self.ptr, force_ms)
+        })
+    }
+}
+
+impl<State> Lame<State> {
+    /// Channel mode. Before `init_params` this is None unless set by
+    /// `set_stereo_mode`; afterwards it is the mode LAME uses.
+    pub fn stereo_mode(&self) -> Option<StereoMode> {
+        let mode = unsafe { ffi::lame_get_mode(self.ptr) };
+        // NOT_SET
+        if mode == 4 {
+            return None;
+        }
+        let force_ms = unsafe { ffi::lame_get_force_ms(self.ptr) } != 0;
+        Some(StereoMode::from_raw(mode, force_ms))
+    }
+}
@@ -0,0 +1,118 @@
+use crate::ffi;
+use crate::{handle_simple_error, Error, Lame, Uninitialized};
+use std::os::raw::c_int;
+
+/// Bitrate mode of the encoder.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VbrMode {
+    /// Constant bitrate. The bitrate is set by `set_kilobitrate`.
+    Off,
+    /// Variable bitrate using the older "rh" algorithm.
+    Rh,
+    /// Average bitrate. The mean bitrate is set by `set_abr_kilobitrate`.
+    Abr,
+    /// Variable bitrate using the "mtrh" algorithm. This is LAME's default
+    /// VBR algorithm, used by the `-V` presets.
+    Mtrh,
+}
+
+impl VbrMode {
+    fn to_raw(self) -> c_int {
+        match self {
+            VbrMode::Off => 0,
+            VbrMode::Rh => 2,
+            VbrMode::Abr => 3,
+            VbrMode::Mtrh => 4,
+        }
+    }
+
+    fn from_raw(vbr_mode: c_int) -> VbrMode {
+        match vbr_mode {
+            2 => VbrMode::Rh,
+            3 => VbrMode::Abr,
+            // vbr_mt (1) is an obsolete alias of vbr_mtrh
+            1 | 4 => VbrMode::Mtrh,
+            _ => VbrMode::Off,
+        }
+    }
+}
+
+impl Lame<Uninitialized> {
+    /// Sets the bitrate mode. Defaults to `VbrMode::Off` (CBR).
+    pub fn set_vbr_mode(&mut self, vbr_mode: VbrMode) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_VBR(self.ptr, vbr_mode.to_raw())
+        })
+    }
+
+    /// Sets the VBR quality. This is a number from 0 to 9 (inclusive), where
+    /// 0 is the highest quality and bitrate (`-V 0`) and 9 is the lowest
+    /// (`-V 9`). Only used in the `Rh` and `Mtrh` modes.
+    pub fn set_vbr_quality(&mut self, quality: u8) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_VBR_q(self.ptr, quality as c_int)
+        })
+    }
+
+    /// Sets the lowest bitrate VBR may use, in kilobits per second.
+    pub fn set_vbr_min_kilobitrate(&mut self, kilobitrate: i32) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_VBR_min_bitrate_kbps(self.ptr, kilobitrate as c_int)
+        })
+    }
+
+    /// Sets the highest bitrate VBR may use, in kilobits per second.
+    pub fn set_vbr_max_kilobitrate(&mut self, kilobitrate: i32) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_VBR_max_bitrate_kbps(self.ptr, kilobitrate as c_int)
+        })
+    }
+
+    /// Enforces the minimum VBR bitrate even for digital silence. By default
+    /// LAME may go below it for silent frames.
+    pub fn set_vbr_hard_min(&mut self, hard_min: bool) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_VBR_hard_min(self.ptr, hard_min as c_int)
+        })
+    }
+
+    /// Sets the mean bitrate targeted in `VbrMode::Abr`, in kilobits per
+    /// second.
+    pub fn set_abr_kilobitrate(&mut self, kilobitrate: i32) -> Result<(), Error> {
+        handle_simple_error(unsafe {
+            ffi::lame_set_VBR_mean_bitrate_kbps(self.ptr, kilobitrate as c_int)
+        })
+    }
+}
+
+impl<State> Lame<State> {
+    /// Bitrate mode. See `set_vbr_mode`.
+    pub fn vbr_mode(&self) -> VbrMode {
+        VbrMode::from_raw(unsafe { ffi::lame_get_VBR(self.ptr) })
+    }
+
+    /// VBR quality. See `set_vbr_quality`.
+    pub fn vbr_quality(&self) -> u8 {
+        unsafe { ffi::lame_get_VBR_q(self.ptr) as u8 }
+    }
+
+    /// Lowest bitrate VBR may use, in kilobits per second.
+    pub fn vbr_min_kilobitrate(&self) -> i32 {
+        unsafe { ffi::lame_get_VBR_min_bitrate_kbps(self.ptr) as i32 }
+    }
+
+    /// Highest bitrate VBR may use, in kilobits per second.
+    pub fn vbr_max_kilobitrate(&self) -> i32 {
+        unsafe { ffi::lame_get_VBR_max_bitrate_kbps(self.ptr) as i32 }
+    }
+
+    /// Whether the minimum VBR bitrate is enforced for digital silence.
+    pub fn vbr_hard_min(&self) -> bool {
+        unsafe { ffi::lame_get_VBR_hard_min(self.ptr) != 0 }
+    }
+
+    /// Mean bitrate targeted in `VbrMode::Abr`, in kilobits per second.
+    pub fn abr_kilobitrate(&self) -> i32 {
+        unsafe { ffi::lame_get_VBR_mean_bitrate_kbps(self.ptr) as i32 }
+    }
+}
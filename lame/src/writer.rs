@@ -0,0 +1,147 @@
+use crate::encode::{Encode, EncodeError};
+use crate::Lame;
+use std::error;
+use std::fmt;
+use std::io::{self, Seek, Write};
+
+#[derive(Debug)]
+pub enum WriteError {
+    Encode(EncodeError),
+    Io(io::Error),
+}
+
+impl From<EncodeError> for WriteError {
+    fn from(err: EncodeError) -> WriteError {
+        WriteError::Encode(err)
+    }
+}
+
+impl From<io::Error> for WriteError {
+    fn from(err: io::Error) -> WriteError {
+        WriteError::Io(err)
+    }
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WriteError::Encode(_) => write!(f, "MP3 encoding failed"),
+            WriteError::Io(_) => write!(f, "I/O error"),
+        }
+    }
+}
+
+impl error::Error for WriteError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            WriteError::Encode(err) => Some(err),
+            WriteError::Io(err) => Some(err),
+        }
+    }
+}
+
+/// Streaming MP3 encoder. Accepts PCM data in chunks and writes the encoded
+/// frames to the underlying writer as they are produced.
+///
+/// The encoder is flushed by `finish`, or when the `Mp3Writer` is dropped.
+/// Errors while flushing on drop are ignored, so call `finish` to handle them.
+pub struct Mp3Writer<W: Write> {
+    lame: Lame,
+    inner: Option<W>,
+    buffer: Vec<u8>,
+    bytes_written: u64,
+}
+
+impl<W: Write> Mp3Writer<W> {
+    /// Creates a writer that encodes with `lame` into `inner`.
+    pub fn new(lame: Lame, inner: W) -> Mp3Writer<W> {
+        Mp3Writer {
+            lame,
+            inner: Some(inner),
+            buffer: Vec::new(),
+            bytes_written: 0,
+        }
+    }
+
+    /// The encoder, for reading its parameters.
+    pub fn lame(&self) -> &Lame {
+        &self.lame
+    }
+
+    /// The underlying writer.
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().unwrap()
+    }
+
+    /// Encodes a chunk of PCM data. See `Encode::encode`.
+    pub fn write<S>(&mut self, pcm_left: &[S], pcm_right: &[S]) -> Result<(), WriteError>
+        where Lame: Encode<S> {
+        self.buffer.clear();
+        self.lame.encode_to_vec(pcm_left, pcm_right, &mut self.buffer)?;
+        self.write_buffer()
+    }
+
+    /// Encodes a chunk of single channel PCM data. See `Encode::encode_mono`.
+    pub fn write_mono<S>(&mut self, pcm: &[S]) -> Result<(), WriteError>
+        where Lame: Encode<S> {
+        self.buffer.clear();
+        self.lame.encode_mono_to_vec(pcm, &mut self.buffer)?;
+        self.write_buffer()
+    }
+
+    /// Encodes a chunk of interleaved PCM data. See
+    /// `Encode::encode_interleaved`.
+    pub fn write_interleaved<S>(&mut self, pcm: &[S]) -> Result<(), WriteError>
+        where Lame: Encode<S> {
+        self.buffer.clear();
+        self.lame.encode_interleaved_to_vec(pcm, &mut self.buffer)?;
+        self.write_buffer()
+    }
+
+    /// Flushes the encoder, writes the final frames and returns the
+    /// underlying writer.
+    pub fn finish(mut self) -> Result<W, WriteError> {
+        // taken first, so that drop does not flush again if this fails
+        let mut inner = self.inner.take().unwrap();
+        self.flush_encoder(&mut inner)?;
+        Ok(inner)
+    }
+
+    fn write_buffer(&mut self) -> Result<(), WriteError> {
+        self.inner.as_mut().unwrap().write_all(&self.buffer)?;
+        self.bytes_written += self.buffer.len() as u64;
+        Ok(())
+    }
+
+    fn flush_encoder(&mut self, inner: &mut W) -> Result<(), WriteError> {
+        self.buffer.clear();
+        self.lame.flush_to_vec(&mut self.buffer)?;
+        inner.write_all(&self.buffer)?;
+        self.bytes_written += self.buffer.len() as u64;
+        inner.flush()?;
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> Mp3Writer<W> {
+    /// Like `finish`, but also writes the LAME/Xing tag over the placeholder
+    /// frame at the start of the stream. See `Lame::write_lametag`.
+    ///
+    /// The writer is left positioned at the end of the stream.
+    pub fn finish_with_tag(mut self) -> Result<W, WriteError> {
+        let mut inner = self.inner.take().unwrap();
+        self.flush_encoder(&mut inner)?;
+
+        let stream_start = inner.stream_position()? - self.bytes_written;
+        self.lame.write_lametag(&mut inner, stream_start)?;
+        Ok(inner)
+    }
+}
+
+impl<W: Write> Drop for Mp3Writer<W> {
+    fn drop(&mut self) {
+        if let Some(mut inner) = self.inner.take() {
+            let _ = self.flush_encoder(&mut inner);
+        }
+    }
+}
@@ -0,0 +1,47 @@
+// Helpers shared by the integration tests. Each test crate uses only some of
+// them.
+#![allow(dead_code)]
+
+use lame::writer::Mp3Writer;
+use lame::{Lame, Uninitialized};
+use std::f32::consts::PI;
+use std::io::Cursor;
+
+pub const SAMPLE_RATE: u32 = 44100;
+
+pub fn sine(len: usize, freq: f32) -> Vec<f32> {
+    (0..len)
+        .map(|i| (2.0 * PI * freq * i as f32 / SAMPLE_RATE as f32).sin() * 0.5)
+        .collect()
+}
+
+/// Initializes `lame` for a 128 kbps stream at `SAMPLE_RATE`.
+pub fn configure(mut lame: Lame<Uninitialized>, channels: u8) -> Lame {
+    lame.set_channels(channels).unwrap();
+    lame.set_sample_rate(SAMPLE_RATE).unwrap();
+    lame.set_kilobitrate(128).unwrap();
+    lame.init_params().unwrap()
+}
+
+/// Encodes `input` through `Mp3Writer` into a stream that starts with a
+/// Xing/Info frame. A mono encoder gets `input` as its only channel.
+pub fn write_stream(lame: Lame, input: &[f32]) -> Vec<u8> {
+    let mut writer = Mp3Writer::new(lame, Cursor::new(Vec::new()));
+    if writer.lame().channels() == 1 {
+        writer.write_mono(input).unwrap();
+    } else {
+        writer.write(input, input).unwrap();
+    }
+    writer.finish_with_tag().unwrap().into_inner()
+}
+
+pub fn encode(input: &[f32], channels: u8) -> Vec<u8> {
+    write_stream(configure(Lame::new().unwrap(), channels), input)
+}
+
+/// Encodes one second of a sine, starting with a Xing/Info frame.
+pub fn encode_second(channels: u8) -> (Vec<f32>, Vec<u8>) {
+    let input = sine(SAMPLE_RATE as usize, 440.0);
+    let mp3 = encode(&input, channels);
+    (input, mp3)
+}
@@ -0,0 +1,62 @@
+// These tests exercise the hip decoder on complete streams.
+#![cfg(feature = "hip")]
+
+mod common;
+
+use common::encode_second;
+use lame::decode::{Decode, HipDecoder};
+
+#[test]
+fn decode_drains_complete_stream() {
+    let (input, mp3) = encode_second(2);
+
+    let mut decoder = HipDecoder::new().unwrap();
+    // room for the encoder delay and padding
+    let mut pcm_l = vec![0_i16; input.len() + 8192];
+    let mut pcm_r = vec![0_i16; input.len() + 8192];
+    let decoded = decoder.decode(&mp3, &mut pcm_l, &mut pcm_r).unwrap();
+    assert!(decoded >= input.len(), "decoded {} of {} samples", decoded, input.len());
+}
+
+#[test]
+fn decode_with_info_drains_mono_stream() {
+    let (input, mp3) = encode_second(1);
+
+    let mut decoder = HipDecoder::new().unwrap();
+    let mut pcm_l = vec![0_i16; input.len() + 8192];
+    let mut pcm_r = vec![0_i16; input.len() + 8192];
+    let info = decoder.decode_with_info(&mp3, &mut pcm_l, &mut pcm_r).unwrap();
+    assert!(info.samples >= input.len(), "decoded {} of {} samples", info.samples, input.len());
+    assert_eq!(info.stream.unwrap().channels, 1);
+    assert!(info.encoder_delay.is_some());
+}
+
+#[test]
+fn decode_f32_drains_complete_stream() {
+    let (input, mp3) = encode_second(2);
+
+    let mut decoder = HipDecoder::new().unwrap();
+    let mut pcm_l = vec![0_f32; input.len() + 8192];
+    let mut pcm_r = vec![0_f32; input.len() + 8192];
+    let decoded = decoder.decode(&mp3, &mut pcm_l, &mut pcm_r).unwrap();
+    assert!(decoded >= input.len(), "decoded {} of {} samples", decoded, input.len());
+    assert!(pcm_l[..decoded].iter().any(|&sample| sample.abs() > 0.25));
+}
+
+// A system libmp3lame does not export the unclipped decoder.
+#[cfg(hip_unclipped)]
+#[test]
+fn decode_f32_keeps_overs() {
+    // the codec rings around the edges of a full scale square wave
+    let input: Vec<f32> = (0..common::SAMPLE_RATE as usize)
+        .map(|i| if i / 50 % 2 == 0 { 1.0 } else { -1.0 })
+        .collect();
+    let mp3 = common::encode(&input, 2);
+
+    let mut decoder = HipDecoder::new().unwrap();
+    let mut pcm_l = vec![0_f32; input.len() + 8192];
+    let mut pcm_r = vec![0_f32; input.len() + 8192];
+    let decoded = decoder.decode(&mp3, &mut pcm_l, &mut pcm_r).unwrap();
+    let peak = pcm_l[..decoded].iter().fold(0_f32, |peak, sample| peak.max(sample.abs()));
+    assert!(peak > 1.0, "decoded peak {}", peak);
+}
@@ -0,0 +1,19 @@
+// These tests check the buffer sizes used by the `_to_vec` helpers.
+
+use lame::encode::Encode;
+use lame::Lame;
+
+#[test]
+fn to_vec_fits_upsampled_output() {
+    let mut lame = Lame::new().unwrap();
+    lame.set_sample_rate(8000).unwrap();
+    lame.set_out_sample_rate(48000).unwrap();
+    lame.set_kilobitrate(320).unwrap();
+    let mut lame = lame.init_params().unwrap();
+
+    let pcm: Vec<i16> = (0..80000).map(|i| ((i * 7919) % 65536 - 32768) as i16).collect();
+    let mut mp3 = Vec::new();
+    let encoded = lame.encode_to_vec(&pcm, &pcm, &mut mp3).unwrap();
+    assert_eq!(encoded, mp3.len());
+    assert!(encoded > 0);
+}
@@ -0,0 +1,65 @@
+// These tests check how the ID3v2 tag written by the encoder stores text.
+
+use lame::encode::Encode;
+use lame::id3tag::{Id3Tag, Id3TagError, Id3Version};
+use lame::Lame;
+
+// Returns the encoding byte and the text of the first `id` frame in the
+// ID3v2 tag at the start of `mp3`.
+fn text_frame<'a>(mp3: &'a [u8], id: &[u8]) -> (u8, &'a [u8]) {
+    assert_eq!(&mp3[..3], b"ID3");
+    let start = mp3.windows(4).position(|window| window == id).unwrap();
+    let size = u32::from_be_bytes(mp3[start + 4..start + 8].try_into().unwrap()) as usize;
+    let data = &mp3[start + 10..start + 10 + size];
+    (data[0], &data[1..])
+}
+
+fn encode_with_tag(tag: &Id3Tag) -> Vec<u8> {
+    let mut lame = Lame::new().unwrap();
+    lame.set_id3tag(tag).unwrap();
+    let mut lame = lame.init_params().unwrap();
+    let pcm = vec![0i16; 1152];
+    let mut mp3 = Vec::new();
+    lame.encode_to_vec(&pcm, &pcm, &mut mp3).unwrap();
+    mp3
+}
+
+#[test]
+fn latin1_text_is_written_as_latin1() {
+    let tag = Id3Tag {
+        title: Some("Café".to_string()),
+        version: Id3Version::V2Only,
+        ..Id3Tag::default()
+    };
+    let mp3 = encode_with_tag(&tag);
+    let (encoding, text) = text_frame(&mp3, b"TIT2");
+    assert_eq!(encoding, 0);
+    assert_eq!(text, b"Caf\xe9");
+}
+
+#[test]
+fn other_text_is_written_as_utf16() {
+    let tag = Id3Tag {
+        artist: Some("Ελένη".to_string()),
+        ..Id3Tag::default()
+    };
+    let mp3 = encode_with_tag(&tag);
+    let (encoding, text) = text_frame(&mp3, b"TPE1");
+    assert_eq!(encoding, 1);
+    let utf16 = text.chunks(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect::<Vec<u16>>();
+    assert_eq!(utf16[0], 0xfeff);
+    assert_eq!(String::from_utf16(&utf16[1..]).unwrap(), "Ελένη");
+}
+
+#[test]
+fn other_text_is_rejected_without_id3v2() {
+    let mut lame = Lame::new().unwrap();
+    let tag = Id3Tag {
+        title: Some("Ελένη".to_string()),
+        version: Id3Version::V1Only,
+        ..Id3Tag::default()
+    };
+    assert!(matches!(lame.set_id3tag(&tag), Err(Id3TagError::InvalidString)));
+}
@@ -0,0 +1,22 @@
+// These tests check how `init_params` validates the encoder parameters.
+
+use lame::mpeg::MpegVersion;
+use lame::{Error, Lame};
+
+#[test]
+fn out_sample_rate_without_bitrate_picks_default() {
+    let mut lame = Lame::new().unwrap();
+    lame.set_out_sample_rate(22050).unwrap();
+    let lame = lame.init_params().unwrap();
+    assert_eq!(lame.out_sample_rate(), 22050);
+    assert_eq!(lame.mpeg_version(), MpegVersion::Mpeg2);
+    assert!(MpegVersion::Mpeg2.kilobitrates().contains(&(lame.kilobitrate() as u32)));
+}
+
+#[test]
+fn out_sample_rate_rejects_unsupported_bitrate() {
+    let mut lame = Lame::new().unwrap();
+    lame.set_out_sample_rate(22050).unwrap();
+    lame.set_kilobitrate(320).unwrap();
+    assert!(matches!(lame.init_params(), Err(Error::BadBitRate)));
+}
@@ -0,0 +1,46 @@
+// These tests exercise the puremp3 decoder on complete streams.
+#![cfg(feature = "puremp3")]
+
+mod common;
+
+use common::{configure, sine, write_stream, SAMPLE_RATE};
+use lame::decode::{Decode, PureMp3Decoder};
+use lame::Lame;
+
+/// Like `common::encode` for a stereo stream, with or without CRCs.
+fn encode(input: &[f32], error_protection: bool) -> Vec<u8> {
+    let mut lame = Lame::new().unwrap();
+    lame.set_error_protection(error_protection).unwrap();
+    write_stream(configure(lame, 2), input)
+}
+
+fn decode(mp3: &[u8], len: usize) -> usize {
+    let mut decoder = PureMp3Decoder::new();
+    // room for the encoder delay and padding
+    let mut pcm_l = vec![0_i16; len + 8192];
+    let mut pcm_r = vec![0_i16; len + 8192];
+    decoder.decode(mp3, &mut pcm_l, &mut pcm_r).unwrap()
+}
+
+#[test]
+fn info_frame_with_crc_is_skipped() {
+    let input = sine(SAMPLE_RATE as usize, 440.0);
+    let plain = decode(&encode(&input, false), input.len());
+    let protected = decode(&encode(&input, true), input.len());
+    assert!(plain >= input.len(), "decoded {} of {} samples", plain, input.len());
+    assert_eq!(protected, plain);
+}
+
+#[test]
+fn info_frame_after_crc_is_skipped() {
+    let input = sine(SAMPLE_RATE as usize, 440.0);
+    let plain = decode(&encode(&input, false), input.len());
+
+    // move the tag behind the CRC, where other encoders put it; the tag ends
+    // well before the end of the frame, so nothing is lost
+    const TAG_OFFSET: usize = 4 + 32;
+    let mut mp3 = encode(&input, true);
+    mp3.copy_within(TAG_OFFSET..TAG_OFFSET + 160, TAG_OFFSET + 2);
+    mp3[TAG_OFFSET..TAG_OFFSET + 2].fill(0);
+    assert_eq!(decode(&mp3, input.len()), plain);
+}
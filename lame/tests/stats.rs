@@ -0,0 +1,20 @@
+// These tests check the estimates reported by `Lame::stats`.
+
+use lame::Lame;
+
+#[test]
+fn total_frames_unknown_without_num_samples() {
+    let lame = Lame::new().unwrap().init_params().unwrap();
+    assert_eq!(lame.stats().total_frames, None);
+}
+
+#[test]
+fn total_frames_estimated_from_num_samples() {
+    let mut lame = Lame::new().unwrap();
+    lame.set_sample_rate(44100).unwrap();
+    lame.set_out_sample_rate(44100).unwrap();
+    lame.set_num_samples(44100).unwrap();
+    let lame = lame.init_params().unwrap();
+    // 38 full frames, then the rest plus the encoder delay and padding
+    assert_eq!(lame.stats().total_frames, Some(40));
+}
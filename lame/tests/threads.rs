@@ -0,0 +1,99 @@
+// These tests exercise the hip decoder alongside the encoder.
+#![cfg(feature = "hip")]
+
+mod common;
+
+use common::{configure, sine, SAMPLE_RATE};
+use lame::decode::{Decode, HipDecoder};
+use lame::encode::{max_encoded_size, Encode};
+use lame::writer::Mp3Writer;
+use lame::{Lame, Uninitialized};
+use std::thread;
+
+const THREADS: usize = 8;
+
+fn assert_send<T: Send>() {}
+
+fn encode(lame: &mut Lame, input: &[f32]) -> Vec<u8> {
+    let mut mp3 = vec![0; max_encoded_size(input.len())];
+    let len = lame.encode_flushing(input, input, &mut mp3).unwrap();
+    mp3.truncate(len);
+    mp3
+}
+
+fn decode(mp3: &[u8], len: usize) -> Vec<i16> {
+    let mut decoder = HipDecoder::new().unwrap();
+    // room for the encoder delay and padding
+    let mut pcm_l = vec![0; len + 8192];
+    let mut pcm_r = vec![0; len + 8192];
+    let decoded = decoder.decode(mp3, &mut pcm_l, &mut pcm_r).unwrap();
+    // comparing empty output would prove nothing
+    assert!(decoded >= len, "decoded {} of {} samples", decoded, len);
+    pcm_l.truncate(decoded);
+    pcm_l
+}
+
+fn encode_decode(input: &[f32]) -> (Vec<u8>, Vec<i16>) {
+    let mut lame = configure(Lame::new().unwrap(), 2);
+    let mp3 = encode(&mut lame, input);
+    let pcm = decode(&mp3, input.len());
+    (mp3, pcm)
+}
+
+#[test]
+fn types_are_send() {
+    assert_send::<Lame<Uninitialized>>();
+    assert_send::<Lame>();
+    assert_send::<HipDecoder>();
+    assert_send::<Mp3Writer<Vec<u8>>>();
+}
+
+#[test]
+fn encoder_moves_between_threads() {
+    let input = sine(SAMPLE_RATE as usize, 440.0);
+    let (expected, _) = encode_decode(&input);
+
+    // created on one thread, configured on a second and used on a third
+    let lame = Lame::new().unwrap();
+    let lame = thread::spawn(move || configure(lame, 2)).join().unwrap();
+    let mp3 = thread::spawn(move || {
+        let mut lame = lame;
+        encode(&mut lame, &input)
+    }).join().unwrap();
+
+    assert_eq!(mp3, expected);
+}
+
+#[test]
+fn concurrent_instances_match_sequential_output() {
+    let inputs: Vec<Vec<f32>> = (0..THREADS)
+        .map(|i| sine(SAMPLE_RATE as usize, 220.0 * (i + 1) as f32))
+        .collect();
+    let expected: Vec<_> = inputs.iter().map(|input| encode_decode(input)).collect();
+
+    let handles: Vec<_> = inputs.into_iter()
+        .map(|input| thread::spawn(move || encode_decode(&input)))
+        .collect();
+    for (handle, expected) in handles.into_iter().zip(expected) {
+        let (mp3, pcm) = handle.join().unwrap();
+        assert_eq!(mp3, expected.0);
+        assert_eq!(pcm, expected.1);
+    }
+}
+
+#[test]
+fn concurrent_decoders_match_sequential_output() {
+    let input = sine(SAMPLE_RATE as usize, 1000.0);
+    let (mp3, expected) = encode_decode(&input);
+
+    let handles: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let mp3 = mp3.clone();
+            let len = input.len();
+            thread::spawn(move || decode(&mp3, len))
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), expected);
+    }
+}
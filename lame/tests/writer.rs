@@ -0,0 +1,33 @@
+// These tests check how `Mp3Writer` finishes the stream.
+
+use lame::writer::{Mp3Writer, WriteError};
+use lame::Lame;
+use std::cell::Cell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+// Accepts all data, but fails every flush and counts them.
+struct FailingFlush {
+    flushes: Rc<Cell<u32>>,
+}
+
+impl Write for FailingFlush {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flushes.set(self.flushes.get() + 1);
+        Err(io::Error::other("flush failed"))
+    }
+}
+
+#[test]
+fn failed_finish_does_not_flush_again_on_drop() {
+    let flushes = Rc::new(Cell::new(0));
+    let lame = Lame::new().unwrap().init_params().unwrap();
+    let mut writer = Mp3Writer::new(lame, FailingFlush { flushes: flushes.clone() });
+    writer.write(&[0i16; 1152], &[0i16; 1152]).unwrap();
+    assert!(matches!(writer.finish(), Err(WriteError::Io(_))));
+    assert_eq!(flushes.get(), 1);
+}
@@ -1,11 +1,21 @@
-use std::borrow::{Borrow, BorrowMut};
+use std::borrow::Borrow;
+use std::fs;
 use std::fs::File;
 use std::io::Write;
 use hound;
 use hound::{SampleFormat, WavSpec};
-use lame::decode::Decode;
+use lame::decode::{Decode, Decoder};
 use lame::Lame;
 use lame::encode::Encode;
+use lame::preset::Preset;
+use lame::stats::EncoderStats;
+use lame::info;
+use lame::mpeg::MpegVersion;
+use lame::reader::Mp3Reader;
+use lame::writer::Mp3Writer;
+
+// interleaved samples encoded at a time
+const CHUNK_SIZE: usize = 4096 * 2;
 
 fn to_string(spec: WavSpec) -> String {
     return format!("{}ch {}Hz {}bit {}",
@@ -19,6 +29,26 @@ fn to_string(spec: WavSpec) -> String {
     );
 }
 
+fn print_stats(stats: &EncoderStats) {
+    println!("Encoded {} frames", stats.frames);
+    for bitrate in stats.bitrates.iter().filter(|bitrate| bitrate.frames > 0) {
+        println!("  {}kbps: {} frames ({} M/S)", bitrate.kilobitrate, bitrate.frames, bitrate.stereo_modes.mid_side);
+    }
+    println!("  L/R: {}, M/S: {}", stats.stereo_modes.left_right, stats.stereo_modes.mid_side);
+}
+
+fn print_replay_gain(lame: &Lame) {
+    if let Some(gain) = lame.radio_gain() {
+        println!("ReplayGain: {:+.1}dB", gain);
+    }
+    if let (Some(peak), Some(gain_change)) = (lame.peak_sample(), lame.noclip_gain_change()) {
+        println!("Peak: {:.3}", peak);
+        if let Some(scale) = lame.noclip_scale() {
+            println!("  clips after decoding, lower by {:.1}dB (scale {:.2})", gain_change, scale);
+        }
+    }
+}
+
 fn main1() {
     // read WAV
     let mut reader = hound::WavReader::open("testresources/se_saa06.wav").unwrap();
@@ -27,35 +57,30 @@ fn main1() {
     println!("length: {} samples", reader.len());
     println!("duration: {}", reader.duration());
 
-    let buffer_l = reader.samples()
-        .step_by(2)
+    let buffer: Vec<i16> = reader.samples()
         .map(|s| s.unwrap()).collect();
-    reader.seek(0).unwrap();
-    let buffer_r = reader.samples()
-        .skip(1).step_by(2)
-        .map(|s| s.unwrap()).collect();
-    let buffer: (Vec<i16>, Vec<i16>) = (buffer_l, buffer_r);
 
     println!("Buffer: ");
-    println!("length: {}, {}", buffer.0.len(), buffer.1.len());
+    println!("length: {}", buffer.len());
 
     // encode to MP3
     let mut lame = Lame::new().unwrap();
-    let kbps = 320;
-    lame.init_params().unwrap();
+    lame.set_preset(Preset::Standard).unwrap();
+    lame.set_report_handler(|level, message| println!("LAME {:?}: {}", level, message)).unwrap();
+    lame.set_find_replay_gain(true).unwrap();
+    lame.set_decode_on_the_fly(true).unwrap();
     lame.set_channels(2).unwrap();
     lame.set_sample_rate(spec.sample_rate).unwrap();
-    lame.set_kilobitrate(kbps).unwrap();
-
-    let buf_length = kbps * 1024 / 8 * (reader.duration() as i32) / (spec.sample_rate as i32) + 1024;
-    println!("buf_length = {}", buf_length);
-    let mut mp3_buffer: Vec<u8> = vec![0_u8; buf_length as usize];
+    let mut lame = lame.init_params().unwrap();
 
-    let mp3_length = lame.encode(buffer.0.borrow(), buffer.1.borrow(), mp3_buffer.borrow_mut()).unwrap();
+    let mut mp3_buffer: Vec<u8> = Vec::new();
+    lame.encode_interleaved_to_vec(buffer.borrow(), &mut mp3_buffer).unwrap();
+    lame.flush_to_vec(&mut mp3_buffer).unwrap();
+    print_stats(&lame.stats());
+    print_replay_gain(&lame);
 
 
     let mut file = File::create("testresources/out.mp3").unwrap();
-    let _ = mp3_buffer.split_off(mp3_length);
     file.write_all(mp3_buffer.borrow()).unwrap();
     file.flush().unwrap();
 
@@ -63,18 +88,21 @@ fn main1() {
     println!("size: {} bytes", mp3_buffer.len());
 
     // decode MP3
-    let (header, samples) = puremp3::read_mp3(&mp3_buffer[..]).expect("Invalid MP3");
-    println!("{}Hz {}bps", header.sample_rate.hz(), header.bitrate.bps());
+    let mut mp3_reader = Mp3Reader::new(&mp3_buffer[..]).unwrap();
 
-    spec.bits_per_sample = 32;
-    spec.sample_format = SampleFormat::Float;
+    spec.bits_per_sample = 16;
+    spec.sample_format = SampleFormat::Int;
     let mut writer = hound::WavWriter::create("testresources/out.wav", spec).unwrap();
-    for (l, r) in samples {
+    for sample in mp3_reader.by_ref() {
+        let (l, r) = sample.expect("Invalid MP3");
         writer.write_sample(l).unwrap();
         writer.write_sample(r).unwrap();
     }
     writer.flush().unwrap();
 
+    let info = mp3_reader.info().expect("Invalid MP3");
+    println!("{}Hz {}kbps", info.sample_rate, info.kilobitrate);
+
 
     // read WAV
     let out_reader = hound::WavReader::open("testresources/out.wav").unwrap();
@@ -93,53 +121,49 @@ fn main2() {
     println!("length: {} samples", reader.len());
     println!("duration: {}", reader.duration());
 
-    let buffer_l = reader.samples()
-        .step_by(2)
-        .map(|s| s.unwrap()).collect();
-    reader.seek(0).unwrap();
-    let buffer_r = reader.samples()
-        .skip(1).step_by(2)
-        .map(|s| s.unwrap()).collect();
-    let buffer: (Vec<f32>, Vec<f32>) = (buffer_l, buffer_r);
-
-    // encode to MP3
+    // encode to MP3, one chunk at a time
     let mut lame = Lame::new().unwrap();
 
     let kbps = 320;
-    lame.init_params().unwrap();
     lame.set_channels(2).unwrap();
     lame.set_sample_rate(spec.sample_rate).unwrap();
     lame.set_kilobitrate(kbps).unwrap();
+    let lame = lame.init_params().unwrap();
     println!("{}kbps", lame.kilobitrate());
 
-    let buf_length = kbps * 1024 / 8 * (reader.duration() as i32) / (spec.sample_rate as i32) + 1024;
-    println!("buf_length = {}", buf_length);
-
-    let mut mp3_buffer: Vec<u8> = vec![0_u8; buf_length as usize];
-    let mp3_length = lame.encode_flushing(buffer.0.borrow(), buffer.1.borrow(), mp3_buffer.borrow_mut()).unwrap();
-    let _ = mp3_buffer.split_off(mp3_length);
-    println!("mp3_length = {}", mp3_length);
-
-    let mut file = File::create("testresources/out.mp3").unwrap();
-    file.write_all(mp3_buffer.borrow()).unwrap();
-    file.flush().unwrap();
+    let file = File::create("testresources/out.mp3").unwrap();
+    let mut mp3_writer = Mp3Writer::new(lame, file);
+    let mut chunk: Vec<f32> = Vec::with_capacity(CHUNK_SIZE);
+    for sample in reader.samples() {
+        chunk.push(sample.unwrap());
+        if chunk.len() == CHUNK_SIZE {
+            mp3_writer.write_interleaved(&chunk).unwrap();
+            chunk.clear();
+        }
+    }
+    mp3_writer.write_interleaved(&chunk).unwrap();
+    mp3_writer.finish_with_tag().unwrap();
 
+    let mp3_buffer = fs::read("testresources/out.mp3").unwrap();
     println!("Out(mp3): ");
     println!("size: {} bytes", mp3_buffer.len());
 
     // decode MP3
-    let (header, samples) = puremp3::read_mp3(&mp3_buffer[..]).expect("Invalid MP3");
-    println!("{}Hz {}bps", header.sample_rate.hz(), header.bitrate.bps());
+    let mut mp3_reader = Mp3Reader::new(&mp3_buffer[..]).unwrap();
 
-    spec.bits_per_sample = 32;
-    spec.sample_format = SampleFormat::Float;
+    spec.bits_per_sample = 16;
+    spec.sample_format = SampleFormat::Int;
     let mut writer = hound::WavWriter::create("testresources/out.wav", spec).unwrap();
-    for (l, r) in samples {
+    for sample in mp3_reader.by_ref() {
+        let (l, r) = sample.expect("Invalid MP3");
         writer.write_sample(l).unwrap();
         writer.write_sample(r).unwrap();
     }
     writer.flush().unwrap();
 
+    let info = mp3_reader.info().expect("Invalid MP3");
+    println!("{}Hz {}kbps", info.sample_rate, info.kilobitrate);
+
     // read WAV
     let out_reader = hound::WavReader::open("testresources/out.wav").unwrap();
     let out_spec = out_reader.spec();
@@ -151,25 +175,52 @@ fn main2() {
 
 fn main3() {
     const LENGTH: usize = 8000;
-    const BYTE_BUF_SIZE: usize = 12500 + 7200;
 
     let mut lame = Lame::new().unwrap();
-    lame.init_params().unwrap();
+    lame.set_find_replay_gain(true).unwrap();
+    lame.set_decode_on_the_fly(true).unwrap();
+    let mut lame = lame.init_params().unwrap();
 
     let input_l = vec![0.5_f32; LENGTH];
     let input_r = vec![0.5_f32; LENGTH];
 
-    let mut bytes = vec![0_u8; BYTE_BUF_SIZE];
-    let len = lame.encode(&input_l[..], &input_r[..], &mut bytes[..]).unwrap();
-    let bytes = &bytes[..len];
+    let mut bytes = Vec::new();
+    lame.encode_to_vec(&input_l[..], &input_r[..], &mut bytes).unwrap();
+    lame.flush_to_vec(&mut bytes).unwrap();
     println!("Encoded: byte_size={}", bytes.len());
+    print_stats(&lame.stats());
+    print_replay_gain(&lame);
 
     let mut output_l = vec![-1_i16; LENGTH * 2];
     let mut output_r = vec![-1_i16; LENGTH * 2];
-    let len = lame.decode(bytes, &mut output_l[..], &mut output_r[..]).unwrap();
+    let len = lame.decode(&bytes, &mut output_l[..], &mut output_r[..]).unwrap();
     println!("Decoded: {} samples", len);
+
+    // cross-check with the pure Rust decoder
+    let mut decoder = Decoder::PureMp3(Box::default());
+    let mut pure_l = vec![-1_i16; LENGTH * 2];
+    let mut pure_r = vec![-1_i16; LENGTH * 2];
+    let pure_len = decoder.decode(&bytes, &mut pure_l[..], &mut pure_r[..]).unwrap();
+    let max_diff = output_l[..len].iter().zip(&pure_l[..pure_len])
+        .map(|(&a, &b)| (a as i32 - b as i32).abs())
+        .max().unwrap_or(0);
+    println!("puremp3: {} samples, max difference {}", pure_len, max_diff);
 }
 
+fn print_info() {
+    println!("LAME {} ({})", info::version(), info::url());
+    for version in [MpegVersion::Mpeg1, MpegVersion::Mpeg2, MpegVersion::Mpeg25] {
+        println!("{:?}: {:?}Hz {:?}kbps", version, info::sample_rates(version), info::kilobitrates(version));
+    }
+}
+
+// `sandbox wav` encodes testresources/se_saa06.wav, `sandbox f32` encodes
+// testresources/f32.wav in chunks, and no argument runs the in-memory check.
 fn main() {
-    main3()
+    print_info();
+    match std::env::args().nth(1).as_deref() {
+        Some("wav") => main1(),
+        Some("f32") => main2(),
+        _ => main3(),
+    }
 }